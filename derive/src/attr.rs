@@ -0,0 +1,127 @@
+/// `#[hdv(...)]` attributes of a field
+#[derive(Default)]
+pub struct FieldAttrs {
+    pub aliases: Vec<String>,
+    /// Column name replacing the identifier
+    pub rename: Option<String>,
+    /// Excluded from the scheme and filled by `Default` on deserialization
+    pub skip: bool,
+    /// Value of the field if its columns are missing from a file
+    pub default: Option<FieldDefault>,
+    /// Nested fields are named without the name of this field
+    pub flatten: bool,
+}
+pub enum FieldDefault {
+    /// `#[hdv(default)]`
+    Default,
+    /// `#[hdv(default = "path")]`
+    Path(syn::ExprPath),
+}
+impl FieldAttrs {
+    pub fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut field_attrs = Self::default();
+        for attr in attrs {
+            if !attr.path().is_ident("hdv") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("alias") {
+                    let alias: syn::LitStr = meta.value()?.parse()?;
+                    field_attrs.aliases.push(alias.value());
+                    return Ok(());
+                }
+                if meta.path.is_ident("rename") {
+                    let rename: syn::LitStr = meta.value()?.parse()?;
+                    field_attrs.rename = Some(rename.value());
+                    return Ok(());
+                }
+                if meta.path.is_ident("flatten") {
+                    field_attrs.flatten = true;
+                    return Ok(());
+                }
+                if meta.path.is_ident("skip") {
+                    field_attrs.skip = true;
+                    return Ok(());
+                }
+                if meta.path.is_ident("default") {
+                    let default = if meta.input.peek(syn::Token![=]) {
+                        let path: syn::LitStr = meta.value()?.parse()?;
+                        FieldDefault::Path(path.parse()?)
+                    } else {
+                        FieldDefault::Default
+                    };
+                    field_attrs.default = Some(default);
+                    return Ok(());
+                }
+                Err(meta.error("unsupported `hdv` field attribute"))
+            })?;
+        }
+        Ok(field_attrs)
+    }
+}
+
+/// `#[hdv(...)]` attributes of a container
+pub struct ContainerAttrs {
+    /// Joins the names of object fields with the names of their nested fields
+    pub separator: String,
+    /// Representation of enum variants
+    pub repr: EnumRepr,
+    /// Name of the variant column of a data-carrying enum
+    pub tag: String,
+    /// `#[hdv(borrow)]`: also implement `HdvDeserializeBorrowed`
+    pub borrow: bool,
+}
+impl Default for ContainerAttrs {
+    fn default() -> Self {
+        Self {
+            separator: hdv::serde::ObjectScheme::DEFAULT_SEPARATOR.to_string(),
+            repr: EnumRepr::String,
+            tag: "tag".to_string(),
+            borrow: false,
+        }
+    }
+}
+#[derive(Clone, Copy)]
+pub enum EnumRepr {
+    /// `#[hdv(repr = "string")]`: variant names
+    String,
+    /// `#[hdv(repr = "u64")]`: discriminants of unit-only enums and variant indices of the others
+    U64,
+}
+impl ContainerAttrs {
+    pub fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut container_attrs = Self::default();
+        for attr in attrs {
+            if !attr.path().is_ident("hdv") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("separator") {
+                    let separator: syn::LitStr = meta.value()?.parse()?;
+                    container_attrs.separator = separator.value();
+                    return Ok(());
+                }
+                if meta.path.is_ident("repr") {
+                    let repr: syn::LitStr = meta.value()?.parse()?;
+                    container_attrs.repr = match repr.value().as_str() {
+                        "string" => EnumRepr::String,
+                        "u64" => EnumRepr::U64,
+                        _ => return Err(meta.error("expected `\"string\"` or `\"u64\"`")),
+                    };
+                    return Ok(());
+                }
+                if meta.path.is_ident("tag") {
+                    let tag: syn::LitStr = meta.value()?.parse()?;
+                    container_attrs.tag = tag.value();
+                    return Ok(());
+                }
+                if meta.path.is_ident("borrow") {
+                    container_attrs.borrow = true;
+                    return Ok(());
+                }
+                Err(meta.error("unsupported `hdv` container attribute"))
+            })?;
+        }
+        Ok(container_attrs)
+    }
+}
@@ -0,0 +1,252 @@
+use crate::{
+    atom_type_type, atom_value_type, attr::EnumRepr, bounded_generics, fetch_values,
+    field_scheme_type, field_schemes, field_type_type, field_values, fill_nulls, hdv_scheme_type,
+    hdv_serialize_type, object_scheme_type, serialize_nullable, write_values, Deserializer, Serde,
+    Variant,
+};
+
+/// Unit-only enums are stored in a single column.
+///
+/// Other enums are stored in a tag column followed by an object column per variant with fields.
+/// The tag alone decides the variant, so the variants have no validity columns
+/// and the atom fields within them are nullable since they are null unless their variant is active.
+pub fn is_unit_only(variants: &[Variant]) -> bool {
+    variants.iter().all(|x| x.is_unit)
+}
+
+/// Value of the tag column of the variant at `index`
+#[allow(non_snake_case)]
+fn tag_value(serde: &Serde, variants: &[Variant], index: usize) -> proc_macro2::TokenStream {
+    let AtomValue = atom_value_type();
+    let ident = &variants[index].ident;
+    let name = ident.to_string();
+    let index = index as u64;
+    match serde.attrs.repr {
+        EnumRepr::String => quote::quote! { #AtomValue::String(#name.into()) },
+        EnumRepr::U64 if is_unit_only(variants) => {
+            quote::quote! { #AtomValue::U64(Self::#ident as u64) }
+        }
+        EnumRepr::U64 => quote::quote! { #AtomValue::U64(#index) },
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn impl_scheme(serde: &Serde, variants: &[Variant]) -> proc_macro2::TokenStream {
+    let HdvScheme = hdv_scheme_type();
+    let ObjectScheme = object_scheme_type();
+    let FieldScheme = field_scheme_type();
+    let FieldType = field_type_type();
+    let AtomType = atom_type_type();
+    let HdvSerialize = hdv_serialize_type();
+    let Name = &serde.name;
+    let generics = bounded_generics(serde, quote::quote! { #HdvScheme + #HdvSerialize });
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let separator = &serde.attrs.separator;
+    let tag_type = match serde.attrs.repr {
+        EnumRepr::String => quote::quote! { #FieldType::Atom(#AtomType::String) },
+        EnumRepr::U64 => quote::quote! { #FieldType::Atom(#AtomType::U64) },
+    };
+
+    if is_unit_only(variants) {
+        return quote::quote! {
+            impl #impl_generics #HdvScheme for #Name #ty_generics #where_clause {
+                fn object_scheme() -> #ObjectScheme {
+                    #ObjectScheme {
+                        fields: vec![#FieldScheme {
+                            name: "0".to_string(),
+                            ty: Self::field_type(),
+                            nullable: false,
+                            default: None,
+                            aliases: vec![],
+                            flatten: false,
+                        }],
+                        separator: #separator.to_string(),
+                    }
+                }
+
+                fn field_type() -> #FieldType {
+                    #tag_type
+                }
+            }
+        };
+    }
+
+    let tag = &serde.attrs.tag;
+    let mut variant_schemes = vec![];
+    for variant in variants.iter().filter(|x| !x.fields.is_empty()) {
+        let name = variant.ident.to_string();
+        let field_schemes = field_schemes(&variant.fields);
+        variant_schemes.push(quote::quote! {
+            #FieldScheme {
+                name: #name.to_string(),
+                ty: #FieldType::Object(#ObjectScheme {
+                    fields: vec![
+                        #( #field_schemes )*
+                    ],
+                    separator: #separator.to_string(),
+                }),
+                nullable: false,
+                default: None,
+                aliases: vec![],
+                flatten: false,
+            },
+        });
+    }
+    quote::quote! {
+        impl #impl_generics #HdvScheme for #Name #ty_generics #where_clause {
+            fn object_scheme() -> #ObjectScheme {
+                let object_scheme = #ObjectScheme {
+                    fields: vec![
+                        #FieldScheme {
+                            name: #tag.to_string(),
+                            ty: #tag_type,
+                            nullable: false,
+                            default: None,
+                            aliases: vec![],
+                            flatten: false,
+                        },
+                        #( #variant_schemes )*
+                    ],
+                    separator: #separator.to_string(),
+                };
+                object_scheme.assert_unique_names();
+                object_scheme
+            }
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn impl_serialize(serde: &Serde, variants: &[Variant]) -> proc_macro2::TokenStream {
+    let HdvSerialize = hdv_serialize_type();
+    let AtomValue = atom_value_type();
+    let Name = &serde.name;
+    let generics = bounded_generics(serde, quote::quote! { #HdvSerialize });
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let mut arms = vec![];
+    for (i, variant) in variants.iter().enumerate() {
+        let ident = &variant.ident;
+        let tag_value = tag_value(serde, variants, i);
+        let bindings = variant.fields.iter().filter(|x| !x.attrs.skip).map(|x| {
+            let member = &x.member;
+            let binding = binding(&x.var);
+            quote::quote! { #member: #binding, }
+        });
+        let mut write_variants = vec![];
+        for (j, other) in variants.iter().enumerate() {
+            if other.fields.is_empty() {
+                continue;
+            }
+            if i == j {
+                let write_values = write_values(&other.fields, |x| {
+                    let binding = binding(&x.var);
+                    quote::quote! { (*#binding) }
+                });
+                write_variants.push(quote::quote! {
+                    #( #write_values )*
+                });
+            } else {
+                let fill_nulls = fill_nulls(&other.fields);
+                write_variants.push(quote::quote! {
+                    #( #fill_nulls )*
+                });
+            }
+        }
+        arms.push(quote::quote! {
+            Self::#ident { #( #bindings )* .. } => {
+                values.push(Some(#tag_value));
+                #( #write_variants )*
+            }
+        });
+    }
+
+    let mut fill_variants = vec![];
+    for variant in variants.iter().filter(|x| !x.fields.is_empty()) {
+        let fill_nulls = fill_nulls(&variant.fields);
+        fill_variants.push(quote::quote! {
+            #( #fill_nulls )*
+        });
+    }
+    let serialize_nullable = serialize_nullable(!is_unit_only(variants));
+
+    quote::quote! {
+        impl #impl_generics #HdvSerialize for #Name #ty_generics #where_clause {
+            fn serialize(&self, values: &mut Vec<Option<#AtomValue>>) {
+                match self {
+                    #( #arms )*
+                }
+            }
+
+            fn fill_nulls(values: &mut Vec<Option<#AtomValue>>) {
+                values.push(None);
+                #( #fill_variants )*
+            }
+
+            #serialize_nullable
+        }
+    }
+}
+fn binding(var: &syn::Ident) -> syn::Ident {
+    quote::format_ident!("__{}", var)
+}
+
+/// Body of the deserializer reading the tag column and then the variants
+pub fn deserialize_body(
+    serde: &Serde,
+    variants: &[Variant],
+    deserializer: Deserializer,
+) -> proc_macro2::TokenStream {
+    let constructor = deserializer.constructor(serde);
+    let mut fetch_variants = vec![];
+    let mut arms = vec![];
+    for (i, variant) in variants.iter().enumerate() {
+        let ident = &variant.ident;
+        let field_values = field_values(&variant.fields, deserializer);
+        let value = if variant.fields.is_empty() {
+            quote::quote! { Some(#constructor::#ident { #( #field_values )* }) }
+        } else {
+            let fetch_values = fetch_values(&variant.fields, deserializer);
+            let variant_value = quote::format_ident!("__variant_{}", i);
+            fetch_variants.push(quote::quote! {
+                let #variant_value = {
+                    #( #fetch_values )*
+                    (|| Some(#constructor::#ident { #( #field_values )* }))()
+                };
+            });
+            quote::quote! { #variant_value }
+        };
+        let pattern = match serde.attrs.repr {
+            EnumRepr::String => {
+                let name = ident.to_string();
+                quote::quote! { #name }
+            }
+            EnumRepr::U64 if is_unit_only(variants) => {
+                quote::quote! { x if x == Self::#ident as u64 }
+            }
+            EnumRepr::U64 => {
+                let index = i as u64;
+                quote::quote! { #index }
+            }
+        };
+        arms.push(quote::quote! { #pattern => #value, });
+    }
+    let tag = match (serde.attrs.repr, deserializer) {
+        (EnumRepr::String, Deserializer::Owned) => quote::quote! { __tag?.string()?.as_ref() },
+        (EnumRepr::String, Deserializer::Borrowed) => quote::quote! { __tag?.string()? },
+        (EnumRepr::U64, _) => quote::quote! { __tag?.u64()? },
+    };
+
+    quote::quote! {
+        let __tag = {
+            let value = __values.first()?.as_ref();
+            *__values = &__values[1..];
+            value
+        };
+        #( #fetch_variants )*
+        match #tag {
+            #( #arms )*
+            _ => None,
+        }
+    }
+}
@@ -1,12 +1,19 @@
+use attr::{ContainerAttrs, FieldAttrs, FieldDefault};
 use hdv::format::AtomType;
 use option::extract_type_from_option;
 
+mod attr;
+mod enums;
 mod option;
 
-#[proc_macro_derive(HdvSerde)]
+#[proc_macro_derive(HdvSerde, attributes(hdv))]
 pub fn serde(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let serde = syn::parse_macro_input!(input as Serde);
 
+    if let Some(field) = newtype_field(&serde) {
+        return impl_newtype(&serde, field).into();
+    }
+
     let impl_scheme = impl_scheme(&serde);
     let impl_serialize = impl_serialize(&serde);
     let impl_deserialize = impl_deserialize(&serde);
@@ -18,92 +25,310 @@ pub fn serde(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     impl_all.into()
 }
 
+/// Generics of the container with `bound` on the type parameters of object fields
+fn bounded_generics(serde: &Serde, bound: proc_macro2::TokenStream) -> syn::Generics {
+    let fields = serde.all_fields();
+    let mut generics = serde.generics.clone();
+    let params: Vec<syn::Ident> = generics.type_params().map(|x| x.ident.clone()).collect();
+    let where_clause = generics.make_where_clause();
+    for param in params {
+        if is_object_param(&fields, &param) {
+            where_clause.predicates.push(syn::parse_quote! {
+                #param: #bound
+            });
+        }
+    }
+    generics
+}
+fn is_object_param(fields: &[&Field], param: &syn::Ident) -> bool {
+    fields.iter().any(|field| {
+        let FieldType::Object(ty) = &field.ty else {
+            return false;
+        };
+        !field.attrs.skip && contains_ident(quote::quote! { #ty }, param)
+    })
+}
+fn contains_ident(tokens: proc_macro2::TokenStream, ident: &syn::Ident) -> bool {
+    tokens.into_iter().any(|x| match x {
+        proc_macro2::TokenTree::Ident(x) => x == *ident,
+        proc_macro2::TokenTree::Group(x) => contains_ident(x.stream(), ident),
+        _ => false,
+    })
+}
+
+/// A newtype wraps a single unnamed non-optional field
+fn newtype_field(serde: &Serde) -> Option<&Field> {
+    let Data::Struct(fields) = &serde.data else {
+        return None;
+    };
+    let [field] = fields.as_slice() else {
+        return None;
+    };
+    let is_newtype =
+        matches!(field.member, syn::Member::Unnamed(_)) && !field.nullable && !field.attrs.skip;
+    is_newtype.then_some(field)
+}
+/// Newtypes are transparent: stored exactly like their inner types
+#[allow(non_snake_case)]
+fn impl_newtype(serde: &Serde, field: &Field) -> proc_macro2::TokenStream {
+    let HdvScheme = hdv_scheme_type();
+    let HdvSerialize = hdv_serialize_type();
+    let ObjectScheme = object_scheme_type();
+    let FieldType = field_type_type();
+    let AtomValue = atom_value_type();
+    let Name = &serde.name;
+    let generics = bounded_generics(serde, quote::quote! { #HdvScheme + #HdvSerialize });
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let Inner = &field.syn_ty;
+    let impl_deserialize = [Deserializer::Owned, Deserializer::Borrowed].map(|deserializer| {
+        let deserialize = deserializer.deserialize(Inner);
+        let deserialize_nullable = deserializer.deserialize_nullable(Inner);
+        impl_deserializer(
+            serde,
+            deserializer,
+            quote::quote! { #deserialize.map(#Name) },
+            quote::quote! { #deserialize_nullable.map(|x| x.map(#Name)) },
+        )
+    });
+    let generics = bounded_generics(serde, quote::quote! { #HdvSerialize });
+    let (impl_generics_serialize, _, where_clause_serialize) = generics.split_for_impl();
+    quote::quote! {
+        impl #impl_generics #HdvScheme for #Name #ty_generics #where_clause {
+            fn object_scheme() -> #ObjectScheme {
+                <#Inner as #HdvScheme>::object_scheme()
+            }
+
+            fn field_type() -> #FieldType {
+                <#Inner as #HdvScheme>::field_type()
+            }
+        }
+        impl #impl_generics_serialize #HdvSerialize for #Name #ty_generics #where_clause_serialize {
+            fn serialize(&self, values: &mut Vec<Option<#AtomValue>>) {
+                #HdvSerialize::serialize(&self.0, values);
+            }
+
+            fn fill_nulls(values: &mut Vec<Option<#AtomValue>>) {
+                <#Inner as #HdvSerialize>::fill_nulls(values);
+            }
+
+            fn serialize_nullable(value: Option<&Self>, values: &mut Vec<Option<#AtomValue>>) {
+                <#Inner as #HdvSerialize>::serialize_nullable(value.map(|x| &x.0), values);
+            }
+
+            fn fill_nullable_nulls(values: &mut Vec<Option<#AtomValue>>) {
+                <#Inner as #HdvSerialize>::fill_nullable_nulls(values);
+            }
+        }
+        #( #impl_deserialize )*
+    }
+}
+
 #[allow(non_snake_case)]
 fn impl_scheme(serde: &Serde) -> proc_macro2::TokenStream {
+    let fields = match &serde.data {
+        Data::Struct(x) => x,
+        Data::Enum(variants) => return enums::impl_scheme(serde, variants),
+    };
+    let field_schemes = field_schemes(fields);
+    let HdvScheme = hdv_scheme_type();
+    let HdvSerialize = hdv_serialize_type();
+    let Name = &serde.name;
+    let generics = bounded_generics(serde, quote::quote! { #HdvScheme + #HdvSerialize });
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let ObjectScheme = object_scheme_type();
+    let separator = &serde.attrs.separator;
+    quote::quote! {
+        impl #impl_generics #HdvScheme for #Name #ty_generics #where_clause {
+            fn object_scheme() -> #ObjectScheme {
+                let object_scheme = #ObjectScheme {
+                    fields: vec![
+                        #( #field_schemes )*
+                    ],
+                    separator: #separator.to_string(),
+                };
+                object_scheme.assert_unique_names();
+                object_scheme
+            }
+        }
+    }
+}
+/// Expressions of `FieldScheme` each followed by a comma
+#[allow(non_snake_case)]
+fn field_schemes(fields: &[Field]) -> Vec<proc_macro2::TokenStream> {
     let HdvScheme = hdv_scheme_type();
     let mut field_schemes = vec![];
-    for field in &serde.fields {
-        let double_quoted_field_name = field.ident.to_string();
+    for field in fields.iter().filter(|x| !x.attrs.skip) {
+        let double_quoted_field_name = match &field.attrs.rename {
+            Some(x) => x.clone(),
+            None => match &field.member {
+                syn::Member::Named(x) => x.to_string(),
+                syn::Member::Unnamed(x) => x.index.to_string(),
+            },
+        };
         let FieldScheme = field_scheme_type();
         let FieldType = field_type_type();
         let ty = match &field.ty {
             FieldType::Object(x) => {
                 quote::quote! {
-                    #FieldType::Object(<#x as #HdvScheme>::object_scheme())
+                    <#x as #HdvScheme>::field_type()
                 }
             }
             FieldType::Atom(x) => {
-                let AtomTypeArm = x.atom_type_arm();
+                let AtomTypeName = x.atom_type_name();
                 let AtomType = atom_type_type();
                 quote::quote! {
-                    #FieldType::Atom(#AtomType::#AtomTypeArm)
+                    #FieldType::Atom(#AtomType::#AtomTypeName)
                 }
             }
         };
-        field_schemes.push(quote::quote! {
+        let nullable = field.nullable;
+        let aliases = &field.attrs.aliases;
+        let flatten = field.attrs.flatten;
+        let field_scheme = quote::quote! {
             #FieldScheme {
                 name: #double_quoted_field_name.to_string(),
                 ty: #ty,
+                nullable: #nullable,
+                default: None,
+                aliases: vec![#( #aliases.to_string() ),*],
+                flatten: #flatten,
+            }
+        };
+        let field_scheme = match field.is_variant {
+            true => quote::quote! {
+                {
+                    let mut field_scheme = #field_scheme;
+                    field_scheme.set_atoms_nullable();
+                    field_scheme
+                }
+            },
+            false => field_scheme,
+        };
+        let Some(default) = &field.attrs.default else {
+            field_schemes.push(quote::quote! { #field_scheme, });
+            continue;
+        };
+        let default = default_value(field, default);
+        let HdvSerialize = hdv_serialize_type();
+        let serialize = if field.nullable {
+            let Inner = extract_type_from_option(&field.syn_ty).unwrap();
+            quote::quote! {
+                <#Inner as #HdvSerialize>::serialize_nullable(default.as_ref(), &mut values);
+            }
+        } else {
+            quote::quote! { #HdvSerialize::serialize(&default, &mut values); }
+        };
+        field_schemes.push(quote::quote! {
+            {
+                let mut field_scheme = #field_scheme;
+                let default = #default;
+                let mut values = vec![];
+                #serialize
+                field_scheme.set_defaults(&mut values.into_iter());
+                field_scheme
             },
         });
     }
+    field_schemes
+}
+#[allow(non_snake_case)]
+fn impl_serialize(serde: &Serde) -> proc_macro2::TokenStream {
+    let fields = match &serde.data {
+        Data::Struct(x) => x,
+        Data::Enum(variants) => return enums::impl_serialize(serde, variants),
+    };
+    let write_values = write_values(fields, |x| {
+        let member = &x.member;
+        quote::quote! { self.#member }
+    });
+    let fill_nulls = fill_nulls(fields);
+    let serialize_nullable = serialize_nullable(true);
+    let HdvSerialize = hdv_serialize_type();
+    let AtomValue = atom_value_type();
     let Name = &serde.name;
-    let ObjectScheme = object_scheme_type();
+    let generics = bounded_generics(serde, quote::quote! { #HdvSerialize });
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
     quote::quote! {
-        impl #HdvScheme for #Name {
-            fn object_scheme() -> #ObjectScheme {
-                #ObjectScheme {
-                    fields: vec![
-                        #( #field_schemes )*
-                    ],
-                }
+        impl #impl_generics #HdvSerialize for #Name #ty_generics #where_clause {
+            fn serialize(&self, values: &mut Vec<Option<#AtomValue>>) {
+                #( #write_values )*
+            }
+
+            fn fill_nulls(values: &mut Vec<Option<#AtomValue>>) {
+                #( #fill_nulls )*
             }
+
+            #serialize_nullable
         }
     }
 }
+/// Methods of `HdvSerialize` for `Option<Self>`, writing the validity column of an object
 #[allow(non_snake_case)]
-fn impl_serialize(serde: &Serde) -> proc_macro2::TokenStream {
+fn serialize_nullable(is_object: bool) -> proc_macro2::TokenStream {
+    let AtomValue = atom_value_type();
+    let (serialize_nullable, fill_nullable_nulls) = match is_object {
+        true => (
+            quote::quote! { hdv::serde::serialize_nullable_object },
+            quote::quote! { hdv::serde::fill_nullable_object_nulls },
+        ),
+        false => (
+            quote::quote! { hdv::serde::serialize_nullable_atom },
+            quote::quote! { hdv::serde::fill_nullable_atom_nulls },
+        ),
+    };
+    quote::quote! {
+        fn serialize_nullable(value: Option<&Self>, values: &mut Vec<Option<#AtomValue>>) {
+            #serialize_nullable(value, values);
+        }
+
+        fn fill_nullable_nulls(values: &mut Vec<Option<#AtomValue>>) {
+            #fill_nullable_nulls::<Self>(values);
+        }
+    }
+}
+/// Statements pushing the values of the fields to `values`
+///
+/// `access` maps a field to its place expression.
+#[allow(non_snake_case)]
+fn write_values(
+    fields: &[Field],
+    access: impl Fn(&Field) -> proc_macro2::TokenStream,
+) -> Vec<proc_macro2::TokenStream> {
     let HdvSerialize = hdv_serialize_type();
     let mut write_values = vec![];
     let AtomValue = atom_value_type();
-    for field in &serde.fields {
-        let field_name = &field.ident;
+    for field in fields.iter().filter(|x| !x.attrs.skip) {
+        let field_value = access(field);
         let write_value = match &field.ty {
             FieldType::Object(Name) => {
                 if field.nullable {
                     quote::quote! {
-                        if let Some(x) = self.#field_name.as_ref() {
-                            #HdvSerialize::serialize(x, values);
-                        } else {
-                            <#Name as #HdvSerialize>::fill_nulls(values);
-                        }
+                        <#Name as #HdvSerialize>::serialize_nullable(#field_value.as_ref(), values);
                     }
                 } else {
-                    quote::quote! { #HdvSerialize::serialize(&self.#field_name, values); }
+                    quote::quote! { #HdvSerialize::serialize(&#field_value, values); }
                 }
             }
             FieldType::Atom(atom_type) => {
                 let AtomTypeArm = atom_type.atom_type_arm();
                 let convert_type = |x: proc_macro2::TokenStream| match &atom_type {
-                    HighLevelAtomType::Compatible(AtomType::String)
-                    | HighLevelAtomType::Compatible(AtomType::Bytes) => {
-                        quote::quote! { #x.clone() }
-                    }
+                    HighLevelAtomType::Shared(_) => quote::quote! { #x.clone() },
+                    HighLevelAtomType::Owned(_) => quote::quote! { (&*#x).into() },
                     HighLevelAtomType::Compatible(_) => quote::quote! { #x as _ },
                 };
                 let atom_option_value = if field.nullable {
-                    let convert_type = convert_type(quote::quote! { x });
                     match &atom_type {
-                        HighLevelAtomType::Compatible(AtomType::String)
-                        | HighLevelAtomType::Compatible(AtomType::Bytes) => {
-                            quote::quote! { self.#field_name.as_ref().map(|x| #AtomValue::#AtomTypeArm(#convert_type)) }
+                        HighLevelAtomType::Shared(_) | HighLevelAtomType::Owned(_) => {
+                            let convert_type = convert_type(quote::quote! { (*x) });
+                            quote::quote! { #field_value.as_ref().map(|x| #AtomValue::#AtomTypeArm(#convert_type)) }
                         }
                         HighLevelAtomType::Compatible(_) => {
-                            quote::quote! { self.#field_name.map(|x| #AtomValue::#AtomTypeArm(#convert_type)) }
+                            let convert_type = convert_type(quote::quote! { x });
+                            quote::quote! { #field_value.map(|x| #AtomValue::#AtomTypeArm(#convert_type)) }
                         }
                     }
                 } else {
-                    let convert_type = convert_type(quote::quote! { self.#field_name });
+                    let convert_type = convert_type(field_value.clone());
                     quote::quote! { Some(#AtomValue::#AtomTypeArm(#convert_type)) }
                 };
                 quote::quote! { values.push(#atom_option_value); }
@@ -111,10 +336,18 @@ fn impl_serialize(serde: &Serde) -> proc_macro2::TokenStream {
         };
         write_values.push(write_value);
     }
-
+    write_values
+}
+/// Statements pushing nulls of the fields to `values`
+#[allow(non_snake_case)]
+fn fill_nulls(fields: &[Field]) -> Vec<proc_macro2::TokenStream> {
+    let HdvSerialize = hdv_serialize_type();
     let mut fill_nulls = vec![];
-    for field in &serde.fields {
+    for field in fields.iter().filter(|x| !x.attrs.skip) {
         let fill_null = match &field.ty {
+            FieldType::Object(Name) if field.nullable => {
+                quote::quote! { <#Name as #HdvSerialize>::fill_nullable_nulls(values); }
+            }
             FieldType::Object(Name) => {
                 quote::quote! { <#Name as #HdvSerialize>::fill_nulls(values); }
             }
@@ -124,29 +357,203 @@ fn impl_serialize(serde: &Serde) -> proc_macro2::TokenStream {
         };
         fill_nulls.push(fill_null);
     }
+    fill_nulls
+}
+/// Flavor of the generated deserializer
+#[derive(Clone, Copy)]
+enum Deserializer {
+    /// `HdvDeserialize` from `AtomValue`s
+    Owned,
+    /// `HdvDeserializeBorrowed` from `AtomValueRef`s
+    Borrowed,
+}
+impl Deserializer {
+    /// Expression deserializing `ty` from `__values`
+    #[allow(non_snake_case)]
+    pub fn deserialize(self, ty: &syn::Type) -> proc_macro2::TokenStream {
+        match self {
+            Deserializer::Owned => {
+                let HdvDeserialize = hdv_deserialize_type();
+                quote::quote! { <#ty as #HdvDeserialize>::deserialize(__values) }
+            }
+            Deserializer::Borrowed => {
+                let HdvDeserializeBorrowed = hdv_deserialize_borrowed_type();
+                quote::quote! { <#ty as #HdvDeserializeBorrowed>::deserialize_borrowed(__values) }
+            }
+        }
+    }
 
+    /// Expression deserializing `Option<ty>` from `__values`
+    #[allow(non_snake_case)]
+    pub fn deserialize_nullable(self, ty: &syn::Type) -> proc_macro2::TokenStream {
+        match self {
+            Deserializer::Owned => {
+                let HdvDeserialize = hdv_deserialize_type();
+                quote::quote! { <#ty as #HdvDeserialize>::deserialize_nullable(__values) }
+            }
+            Deserializer::Borrowed => {
+                let HdvDeserializeBorrowed = hdv_deserialize_borrowed_type();
+                quote::quote! { <#ty as #HdvDeserializeBorrowed>::deserialize_borrowed_nullable(__values) }
+            }
+        }
+    }
+
+    /// Expression deserializing `Option<Self>` from `__values`, reading the validity column of an object
+    pub fn deserialize_nullable_self(self, is_object: bool) -> proc_macro2::TokenStream {
+        match (self, is_object) {
+            (Deserializer::Owned, true) => {
+                quote::quote! { hdv::serde::deserialize_nullable_object(__values) }
+            }
+            (Deserializer::Owned, false) => {
+                quote::quote! { hdv::serde::deserialize_nullable_atom(__values) }
+            }
+            (Deserializer::Borrowed, true) => {
+                quote::quote! { hdv::serde::deserialize_borrowed_nullable_object::<Self>(__values) }
+            }
+            (Deserializer::Borrowed, false) => {
+                quote::quote! { hdv::serde::deserialize_borrowed_nullable_atom::<Self>(__values) }
+            }
+        }
+    }
+
+    /// Path constructing the container
+    ///
+    /// The borrowed value is not `Self` but the container with other generic arguments.
+    pub fn constructor(self, serde: &Serde) -> proc_macro2::TokenStream {
+        match self {
+            Deserializer::Owned => quote::quote! { Self },
+            Deserializer::Borrowed => {
+                let name = &serde.name;
+                quote::quote! { #name }
+            }
+        }
+    }
+}
+fn impl_deserialize(serde: &Serde) -> proc_macro2::TokenStream {
+    [Deserializer::Owned, Deserializer::Borrowed]
+        .into_iter()
+        .map(|deserializer| {
+            let body = match &serde.data {
+                Data::Struct(fields) => {
+                    let fetch_values = fetch_values(fields, deserializer);
+                    let field_values = field_values(fields, deserializer);
+                    let constructor = deserializer.constructor(serde);
+                    quote::quote! {
+                        #( #fetch_values )*
+                        Some(#constructor {
+                            #( #field_values )*
+                        })
+                    }
+                }
+                Data::Enum(variants) => enums::deserialize_body(serde, variants, deserializer),
+            };
+            let is_object = match &serde.data {
+                Data::Struct(_) => true,
+                Data::Enum(variants) => !enums::is_unit_only(variants),
+            };
+            let deserialize_nullable = deserializer.deserialize_nullable_self(is_object);
+            impl_deserializer(serde, deserializer, body, deserialize_nullable)
+        })
+        .collect()
+}
+/// Impl of the deserializer trait around `body` reading `__values`
+///
+/// `nullable` is the body deserializing `Option<Self>`.
+///
+/// Containers with lifetimes only implement `HdvDeserializeBorrowed`.
+/// Other containers implement it only with `#[hdv(borrow)]`
+/// since their object fields might not implement it.
+/// Containers with bounds on their type parameters only implement `HdvDeserialize`
+/// since the bounds might not hold on the borrowed type arguments.
+#[allow(non_snake_case)]
+fn impl_deserializer(
+    serde: &Serde,
+    deserializer: Deserializer,
+    body: proc_macro2::TokenStream,
+    nullable: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
     let Name = &serde.name;
-    quote::quote! {
-        impl #HdvSerialize for #Name {
-            fn serialize(&self, values: &mut Vec<Option<#AtomValue>>) {
-                #( #write_values )*
+    match deserializer {
+        Deserializer::Owned => {
+            if serde.generics.lifetimes().next().is_some() {
+                return quote::quote! {};
             }
+            let HdvDeserialize = hdv_deserialize_type();
+            let AtomValue = atom_value_type();
+            let generics = bounded_generics(serde, quote::quote! { #HdvDeserialize });
+            let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+            quote::quote! {
+                impl #impl_generics #HdvDeserialize for #Name #ty_generics #where_clause {
+                    fn deserialize(__values: &mut &[Option<#AtomValue>]) -> Option<Self> {
+                        #body
+                    }
 
-            fn fill_nulls(values: &mut Vec<Option<#AtomValue>>) {
-                #( #fill_nulls )*
+                    fn deserialize_nullable(__values: &mut &[Option<#AtomValue>]) -> Option<Option<Self>> {
+                        #nullable
+                    }
+                }
+            }
+        }
+        Deserializer::Borrowed => {
+            let is_requested = serde.attrs.borrow || serde.generics.lifetimes().next().is_some();
+            let is_bounded = serde.generics.where_clause.is_some()
+                || serde.generics.type_params().any(|x| !x.bounds.is_empty());
+            if !is_requested || is_bounded {
+                return quote::quote! {};
+            }
+            let HdvDeserializeBorrowed = hdv_deserialize_borrowed_type();
+            let AtomValueRef = atom_value_ref_type();
+            let generics = bounded_generics(serde, quote::quote! { #HdvDeserializeBorrowed });
+            let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+            let fields = serde.all_fields();
+            let borrowed_args = serde.generics.params.iter().map(|param| match param {
+                syn::GenericParam::Lifetime(_) => quote::quote! { '__a },
+                syn::GenericParam::Type(x) => {
+                    let ident = &x.ident;
+                    if is_object_param(&fields, ident) {
+                        quote::quote! { <#ident as #HdvDeserializeBorrowed>::Borrowed<'__a> }
+                    } else {
+                        quote::quote! { #ident }
+                    }
+                }
+                syn::GenericParam::Const(x) => {
+                    let ident = &x.ident;
+                    quote::quote! { #ident }
+                }
+            });
+            quote::quote! {
+                impl #impl_generics #HdvDeserializeBorrowed for #Name #ty_generics #where_clause {
+                    type Borrowed<'__a> = #Name<#( #borrowed_args ),*>;
+                    fn deserialize_borrowed<'__a>(
+                        __values: &mut &[Option<#AtomValueRef<'__a>>],
+                    ) -> Option<Self::Borrowed<'__a>> {
+                        #body
+                    }
+
+                    fn deserialize_borrowed_nullable<'__a>(
+                        __values: &mut &[Option<#AtomValueRef<'__a>>],
+                    ) -> Option<Option<Self::Borrowed<'__a>>> {
+                        #nullable
+                    }
+                }
             }
         }
     }
 }
+/// Statements taking the values of the fields from `__values` into the local variables of the fields
 #[allow(non_snake_case)]
-fn impl_deserialize(serde: &Serde) -> proc_macro2::TokenStream {
-    let HdvDeserialize = hdv_deserialize_type();
+fn fetch_values(fields: &[Field], deserializer: Deserializer) -> Vec<proc_macro2::TokenStream> {
     let mut fetch_values = vec![];
-    for field in &serde.fields {
-        let field_name = &field.ident;
+    for field in fields.iter().filter(|x| !x.attrs.skip) {
+        let field_name = &field.var;
         let fetch_value = match &field.ty {
             FieldType::Object(Name) => {
-                quote::quote! { let #field_name = <#Name as #HdvDeserialize>::deserialize(__values); }
+                let deserialize = if field.nullable {
+                    deserializer.deserialize_nullable(Name)
+                } else {
+                    deserializer.deserialize(Name)
+                };
+                quote::quote! { let #field_name = #deserialize; }
             }
             FieldType::Atom(_) => {
                 quote::quote! {
@@ -160,23 +567,39 @@ fn impl_deserialize(serde: &Serde) -> proc_macro2::TokenStream {
         };
         fetch_values.push(fetch_value);
     }
-    let mut fields = vec![];
-    for field in &serde.fields {
-        let field_name = &field.ident;
+    fetch_values
+}
+/// Field initializers of the struct expression from the local variables of [`fetch_values`]
+fn field_values(fields: &[Field], deserializer: Deserializer) -> Vec<proc_macro2::TokenStream> {
+    let mut field_values = vec![];
+    for field in fields {
+        let member = &field.member;
+        let field_name = &field.var;
+        if field.attrs.skip {
+            field_values.push(quote::quote! { #member: Default::default(), });
+            continue;
+        }
         let field_value = match &field.ty {
-            FieldType::Object(_) => {
-                if field.nullable {
-                    quote::quote! { #field_name }
-                } else {
-                    quote::quote! { #field_name? }
-                }
-            }
+            // Nullable objects are `None` only if present but invalid
+            FieldType::Object(_) => quote::quote! { #field_name? },
             FieldType::Atom(x) => {
                 let atom_type_get = x.atom_type_get();
                 let convert_type = |atom_value: proc_macro2::TokenStream| match &x {
-                    HighLevelAtomType::Compatible(AtomType::String)
-                    | HighLevelAtomType::Compatible(AtomType::Bytes) => {
-                        quote::quote! { #atom_value.#atom_type_get.unwrap().to_owned() }
+                    HighLevelAtomType::Shared(_) | HighLevelAtomType::Owned(_)
+                        if matches!(deserializer, Deserializer::Borrowed) =>
+                    {
+                        quote::quote! { #atom_value.#atom_type_get.unwrap().into() }
+                    }
+                    HighLevelAtomType::Shared(_) => {
+                        quote::quote! { #atom_value.#atom_type_get.unwrap().clone() }
+                    }
+                    HighLevelAtomType::Owned(_) => {
+                        quote::quote! { (&**#atom_value.#atom_type_get.unwrap()).into() }
+                    }
+                    HighLevelAtomType::Compatible(x) if x.int_range().is_some() => {
+                        quote::quote! {
+                            ::core::convert::TryFrom::try_from(#atom_value.#atom_type_get.unwrap()).ok()?
+                        }
                     }
                     HighLevelAtomType::Compatible(_) => {
                         quote::quote! { #atom_value.#atom_type_get.unwrap() as _ }
@@ -185,7 +608,10 @@ fn impl_deserialize(serde: &Serde) -> proc_macro2::TokenStream {
                 if field.nullable {
                     let convert_type = convert_type(quote::quote! { x });
                     quote::quote! {
-                        #field_name.map(|x| #convert_type)
+                        match #field_name {
+                            Some(x) => Some(#convert_type),
+                            None => None,
+                        }
                     }
                 } else {
                     let convert_type = convert_type(quote::quote! { #field_name? });
@@ -193,19 +619,19 @@ fn impl_deserialize(serde: &Serde) -> proc_macro2::TokenStream {
                 }
             }
         };
-        fields.push(quote::quote! { #field_name: #field_value, });
+        field_values.push(quote::quote! { #member: #field_value, });
     }
-    let Name = &serde.name;
-    let AtomValue = atom_value_type();
-    quote::quote! {
-        impl #HdvDeserialize for #Name {
-            fn deserialize(__values: &mut &[Option<#AtomValue>]) -> Option<Self> {
-                #( #fetch_values )*
-                Some(Self {
-                    #( #fields )*
-                })
-            }
+    field_values
+}
+
+#[allow(non_snake_case)]
+fn default_value(field: &Field, default: &FieldDefault) -> proc_macro2::TokenStream {
+    match default {
+        FieldDefault::Default => {
+            let Ty = &field.syn_ty;
+            quote::quote! { <#Ty as Default>::default() }
         }
+        FieldDefault::Path(path) => quote::quote! { #path() },
     }
 }
 
@@ -224,6 +650,11 @@ fn hdv_deserialize_type() -> proc_macro2::TokenStream {
         hdv::serde::HdvDeserialize
     }
 }
+fn hdv_deserialize_borrowed_type() -> proc_macro2::TokenStream {
+    quote::quote! {
+        hdv::serde::HdvDeserializeBorrowed
+    }
+}
 fn object_scheme_type() -> proc_macro2::TokenStream {
     quote::quote! {
         hdv::serde::ObjectScheme
@@ -249,10 +680,29 @@ fn atom_value_type() -> proc_macro2::TokenStream {
         hdv::format::AtomValue
     }
 }
+fn atom_value_ref_type() -> proc_macro2::TokenStream {
+    quote::quote! {
+        hdv::format::AtomValueRef
+    }
+}
 
 struct Serde {
     pub name: syn::Ident,
-    pub fields: Vec<Field>,
+    pub generics: syn::Generics,
+    pub data: Data,
+    pub attrs: ContainerAttrs,
+}
+impl Serde {
+    pub fn all_fields(&self) -> Vec<&Field> {
+        match &self.data {
+            Data::Struct(fields) => fields.iter().collect(),
+            Data::Enum(variants) => variants.iter().flat_map(|x| &x.fields).collect(),
+        }
+    }
+}
+enum Data {
+    Struct(Vec<Field>),
+    Enum(Vec<Variant>),
 }
 impl syn::parse::Parse for Serde {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
@@ -269,40 +719,65 @@ impl syn::parse::Parse for Serde {
                 ));
             }
         };
-        let structure = match item {
-            syn::Item::Struct(x) => x,
+        let (name, generics, attrs, data) = match item {
+            syn::Item::Struct(x) => {
+                let fields = parse_fields(&x.fields, false)?;
+                (x.ident, x.generics, x.attrs, Data::Struct(fields))
+            }
+            syn::Item::Enum(x) => {
+                let mut variants = vec![];
+                for variant in &x.variants {
+                    let fields = parse_fields(&variant.fields, true)?;
+                    variants.push(Variant {
+                        ident: variant.ident.clone(),
+                        is_unit: matches!(variant.fields, syn::Fields::Unit),
+                        fields,
+                    });
+                }
+                (x.ident, x.generics, x.attrs, Data::Enum(variants))
+            }
             _ => {
                 return Err(syn::Error::new(
                     input.span(),
                     format!(
-                        "`item` expected `Item::Struct`, found `{}`",
+                        "`item` expected `Item::Struct` or `Item::Enum`, found `{}`",
                         quote::quote! { #item }
                     ),
                 ));
             }
         };
-        let name = structure.ident;
-        let mut fields = vec![];
-        for field in &structure.fields {
-            let ident = &field.ident;
-            let Some(ident) = ident else {
-                return Err(syn::Error::new(
-                    input.span(),
-                    format!(
-                        "`ident` expected `Some`, found `{}`",
-                        quote::quote! { #ident }
-                    ),
-                ));
-            };
-            let (ty, nullable) = field_type(&field.ty)?;
-            fields.push(Field {
-                ident: ident.clone(),
-                ty,
-                nullable,
-            })
-        }
-        Ok(Self { name, fields })
+        let attrs = ContainerAttrs::parse(&attrs)?;
+        Ok(Self {
+            name,
+            generics,
+            data,
+            attrs,
+        })
+    }
+}
+fn parse_fields(fields: &syn::Fields, is_variant: bool) -> syn::Result<Vec<Field>> {
+    let mut parsed = vec![];
+    for (i, field) in fields.iter().enumerate() {
+        let (member, var) = match &field.ident {
+            Some(ident) => (syn::Member::Named(ident.clone()), ident.clone()),
+            None => (
+                syn::Member::Unnamed(i.into()),
+                quote::format_ident!("__field_{}", i),
+            ),
+        };
+        let (ty, nullable) = field_type(&field.ty)?;
+        let attrs = FieldAttrs::parse(&field.attrs)?;
+        parsed.push(Field {
+            member,
+            var,
+            syn_ty: field.ty.clone(),
+            ty,
+            nullable,
+            is_variant,
+            attrs,
+        })
     }
+    Ok(parsed)
 }
 
 fn field_type(ty: &syn::Type) -> syn::Result<(FieldType, bool)> {
@@ -310,28 +785,96 @@ fn field_type(ty: &syn::Type) -> syn::Result<(FieldType, bool)> {
         Some(ty) => (ty, true),
         None => (ty, false),
     };
-    let str = quote::quote! { #ty }.to_string();
-    let field_type = match str.as_str() {
-        "u8" | "u16" | "u32" | "u64" => {
-            FieldType::Atom(HighLevelAtomType::Compatible(AtomType::U64))
+    let field_type = match atom_type(ty) {
+        Some(x) => FieldType::Atom(x),
+        None => FieldType::Object(ty.clone()),
+    };
+    Ok((field_type, nullable))
+}
+
+/// Recognize atom types by the last segment of their paths.
+///
+/// Other types, including aliases of atom types, lists and maps, resolve through their `HdvScheme` impls.
+fn atom_type(ty: &syn::Type) -> Option<HighLevelAtomType> {
+    let syn::Type::Path(type_path) = ty else {
+        return None;
+    };
+    if type_path.qself.is_some() {
+        return None;
+    }
+    let segment = type_path.path.segments.last()?;
+    let ident = segment.ident.to_string();
+    let arg = match &segment.arguments {
+        syn::PathArguments::None => None,
+        syn::PathArguments::AngleBracketed(args) => {
+            if args.args.len() != 1 {
+                return None;
+            }
+            match args.args.first()? {
+                syn::GenericArgument::Type(x) => Some(x),
+                _ => return None,
+            }
         }
-        "i8" | "i16" | "i32" | "i64" => {
-            FieldType::Atom(HighLevelAtomType::Compatible(AtomType::I64))
+        syn::PathArguments::Parenthesized(_) => return None,
+    };
+    let Some(arg) = arg else {
+        return Some(match ident.as_str() {
+            "u8" => HighLevelAtomType::Compatible(AtomType::U8),
+            "u16" => HighLevelAtomType::Compatible(AtomType::U16),
+            "u32" => HighLevelAtomType::Compatible(AtomType::U32),
+            "u64" | "usize" => HighLevelAtomType::Compatible(AtomType::U64),
+            "i8" => HighLevelAtomType::Compatible(AtomType::I8),
+            "i16" => HighLevelAtomType::Compatible(AtomType::I16),
+            "i32" => HighLevelAtomType::Compatible(AtomType::I32),
+            "i64" | "isize" => HighLevelAtomType::Compatible(AtomType::I64),
+            "f32" => HighLevelAtomType::Compatible(AtomType::F32),
+            "f64" => HighLevelAtomType::Compatible(AtomType::F64),
+            "bool" => HighLevelAtomType::Compatible(AtomType::Bool),
+            "String" => HighLevelAtomType::Owned(AtomType::String),
+            _ => return None,
+        });
+    };
+    let is_ident = |ty: &syn::Type, name: &str| match ty {
+        syn::Type::Path(x) => x.qself.is_none() && x.path.is_ident(name),
+        _ => false,
+    };
+    let is_bytes = match arg {
+        syn::Type::Slice(x) => is_ident(&x.elem, "u8"),
+        _ => false,
+    };
+    let atom_type = if is_ident(arg, "str") {
+        AtomType::String
+    } else if is_bytes {
+        AtomType::Bytes
+    } else {
+        if ident == "Vec" && is_ident(arg, "u8") {
+            return Some(HighLevelAtomType::Owned(AtomType::Bytes));
         }
-        "f32" => FieldType::Atom(HighLevelAtomType::Compatible(AtomType::F32)),
-        "f64" => FieldType::Atom(HighLevelAtomType::Compatible(AtomType::F64)),
-        "Arc < [u8] >" => FieldType::Atom(HighLevelAtomType::Compatible(AtomType::Bytes)),
-        "Arc < str >" => FieldType::Atom(HighLevelAtomType::Compatible(AtomType::String)),
-        "String" => FieldType::Atom(HighLevelAtomType::Compatible(AtomType::String)),
-        _ => FieldType::Object(ty.clone()),
+        return None;
     };
-    Ok((field_type, nullable))
+    Some(match ident.as_str() {
+        "Arc" => HighLevelAtomType::Shared(atom_type),
+        "Box" | "Rc" => HighLevelAtomType::Owned(atom_type),
+        _ => return None,
+    })
 }
 
-struct Field {
+struct Variant {
     pub ident: syn::Ident,
+    pub is_unit: bool,
+    pub fields: Vec<Field>,
+}
+struct Field {
+    /// Named or positional
+    pub member: syn::Member,
+    /// Local variable holding the field
+    pub var: syn::Ident,
+    pub syn_ty: syn::Type,
     pub ty: FieldType,
     pub nullable: bool,
+    /// Field of an enum variant, null unless the variant is active
+    pub is_variant: bool,
+    pub attrs: FieldAttrs,
 }
 #[allow(clippy::large_enum_variant)]
 enum FieldType {
@@ -339,58 +882,65 @@ enum FieldType {
     Atom(HighLevelAtomType),
 }
 enum HighLevelAtomType {
+    /// Converted with `as`, or checked with `TryFrom` for integers
     Compatible(AtomType),
+    /// `Arc<str>` or `Arc<[u8]>` sharing the buffer of the atom value
+    Shared(AtomType),
+    /// Other containers of `str` or `[u8]` converted with `From`
+    Owned(AtomType),
 }
 impl HighLevelAtomType {
-    pub fn atom_type_arm(&self) -> proc_macro2::TokenStream {
+    pub fn atom_type(&self) -> AtomType {
         match self {
-            HighLevelAtomType::Compatible(AtomType::String) => {
-                quote::quote! { String }
-            }
-            HighLevelAtomType::Compatible(AtomType::Bytes) => {
-                quote::quote! { Bytes }
-            }
-            HighLevelAtomType::Compatible(AtomType::F32) => {
-                quote::quote! { F32 }
-            }
-            HighLevelAtomType::Compatible(AtomType::F64) => {
-                quote::quote! { F64 }
-            }
-            HighLevelAtomType::Compatible(AtomType::I64) => {
-                quote::quote! { I64 }
-            }
-            HighLevelAtomType::Compatible(AtomType::U64) => {
-                quote::quote! { U64 }
-            }
-            HighLevelAtomType::Compatible(AtomType::Bool) => {
-                quote::quote! { Bool }
-            }
+            HighLevelAtomType::Compatible(x)
+            | HighLevelAtomType::Shared(x)
+            | HighLevelAtomType::Owned(x) => x.clone(),
+        }
+    }
+
+    /// Variant of the atom type in the schema
+    pub fn atom_type_name(&self) -> proc_macro2::TokenStream {
+        match self.atom_type() {
+            AtomType::U8 => quote::quote! { U8 },
+            AtomType::U16 => quote::quote! { U16 },
+            AtomType::U32 => quote::quote! { U32 },
+            AtomType::I8 => quote::quote! { I8 },
+            AtomType::I16 => quote::quote! { I16 },
+            AtomType::I32 => quote::quote! { I32 },
+            _ => self.atom_type_arm(),
+        }
+    }
+
+    /// Variant of the atom value
+    pub fn atom_type_arm(&self) -> proc_macro2::TokenStream {
+        match self.atom_type() {
+            AtomType::String => quote::quote! { String },
+            AtomType::Bytes => quote::quote! { Bytes },
+            AtomType::F32 => quote::quote! { F32 },
+            AtomType::F64 => quote::quote! { F64 },
+            AtomType::I8 | AtomType::I16 | AtomType::I32 | AtomType::I64 => quote::quote! { I64 },
+            AtomType::U8 | AtomType::U16 | AtomType::U32 | AtomType::U64 => quote::quote! { U64 },
+            AtomType::Bool => quote::quote! { Bool },
+            // Other atom types resolve through their `HdvScheme` impls
+            _ => unreachable!(),
         }
     }
 
     pub fn atom_type_get(&self) -> proc_macro2::TokenStream {
-        match self {
-            HighLevelAtomType::Compatible(AtomType::String) => {
-                quote::quote! { string() }
-            }
-            HighLevelAtomType::Compatible(AtomType::Bytes) => {
-                quote::quote! { bytes() }
-            }
-            HighLevelAtomType::Compatible(AtomType::F32) => {
-                quote::quote! { f32() }
-            }
-            HighLevelAtomType::Compatible(AtomType::F64) => {
-                quote::quote! { f64() }
-            }
-            HighLevelAtomType::Compatible(AtomType::I64) => {
+        match self.atom_type() {
+            AtomType::String => quote::quote! { string() },
+            AtomType::Bytes => quote::quote! { bytes() },
+            AtomType::F32 => quote::quote! { f32() },
+            AtomType::F64 => quote::quote! { f64() },
+            AtomType::I8 | AtomType::I16 | AtomType::I32 | AtomType::I64 => {
                 quote::quote! { i64() }
             }
-            HighLevelAtomType::Compatible(AtomType::U64) => {
+            AtomType::U8 | AtomType::U16 | AtomType::U32 | AtomType::U64 => {
                 quote::quote! { u64() }
             }
-            HighLevelAtomType::Compatible(AtomType::Bool) => {
-                quote::quote! { bool() }
-            }
+            AtomType::Bool => quote::quote! { bool() },
+            // Other atom types resolve through their `HdvScheme` impls
+            _ => unreachable!(),
         }
     }
 }
@@ -0,0 +1,343 @@
+//! Rust structs generated from the headers of files
+
+use std::collections::HashSet;
+
+use crate::{
+    format::{AtomScheme, AtomType, ItemType},
+    serde::{FieldType, ObjectScheme},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderFormat {
+    Bin,
+    Text,
+}
+
+/// Crate of the types of temporal columns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemporalTypes {
+    /// Only UTC timestamps and durations are supported.
+    Std,
+    Chrono,
+    Time,
+}
+
+#[derive(Debug, Clone)]
+pub struct RustCodegenOptions {
+    /// Name of the struct of a whole row
+    ///
+    /// Nested structs are named after it and the paths of their fields.
+    pub root_name: String,
+    pub temporal_types: TemporalTypes,
+}
+impl Default for RustCodegenOptions {
+    fn default() -> Self {
+        Self {
+            root_name: "Row".to_string(),
+            temporal_types: TemporalTypes::Std,
+        }
+    }
+}
+
+/// Read the header at the start of a file.
+pub fn read_header<R>(read: &mut R, format: HeaderFormat) -> std::io::Result<Vec<AtomScheme>>
+where
+    R: std::io::BufRead,
+{
+    match format {
+        HeaderFormat::Bin => crate::io::bin::read_header(read),
+        HeaderFormat::Text => crate::io::text::read_header(read, &mut String::new()),
+    }
+}
+
+/// Generate the Rust source of the file at `input` and write it to `output`, for build scripts.
+pub fn generate_file(
+    input: impl AsRef<std::path::Path>,
+    format: HeaderFormat,
+    output: impl AsRef<std::path::Path>,
+    options: &RustCodegenOptions,
+) -> std::io::Result<()> {
+    let file = std::fs::File::open(input)?;
+    let header = read_header(&mut std::io::BufReader::new(file), format)?;
+    let code = generate_rust(&header, options)?;
+    std::fs::write(output, code)
+}
+
+/// Generate structs deriving `HdvSerde` whose columns are of `header`
+///
+/// Atom fields are optional since headers carry no nullability.
+/// A nullable object of a validity column is optional too.
+/// Names that are not Rust identifiers are kept by `#[hdv(rename = "...")]`.
+///
+/// Return an error of [`std::io::ErrorKind::InvalidData`] if the paths of the columns conflict
+/// and of [`std::io::ErrorKind::Unsupported`] if a column has no matching Rust type.
+pub fn generate_rust(
+    header: &[AtomScheme],
+    options: &RustCodegenOptions,
+) -> std::io::Result<String> {
+    let object = ObjectScheme::from_atom_schemes(header)?;
+    let mut generator = Generator {
+        options,
+        struct_names: HashSet::new(),
+        structs: vec![],
+    };
+    generator.push_struct(&object, &options.root_name, "")?;
+    let mut code = "// Generated from the header of a file by `hdv::codegen`\n".to_string();
+    for item in generator.structs {
+        code.push('\n');
+        code.push_str(&item);
+    }
+    Ok(code)
+}
+
+struct Generator<'a> {
+    options: &'a RustCodegenOptions,
+    struct_names: HashSet<String>,
+    structs: Vec<String>,
+}
+impl Generator<'_> {
+    /// Return the unique name of the struct.
+    ///
+    /// `path` is the full name of the object field, used in errors.
+    fn push_struct(
+        &mut self,
+        object: &ObjectScheme,
+        name: &str,
+        path: &str,
+    ) -> std::io::Result<String> {
+        let name = unique(name.to_string(), &mut self.struct_names);
+        let index = self.structs.len();
+        self.structs.push(String::new());
+        let mut idents = HashSet::new();
+        let mut body = String::new();
+        for field in &object.fields {
+            let full_name = match path.is_empty() {
+                true => field.name.clone(),
+                false => format!("{path}{}{}", object.separator, field.name),
+            };
+            let ty = match &field.ty {
+                FieldType::Atom(ty) => {
+                    rust_type(ty, self.options.temporal_types).ok_or_else(|| {
+                        let message = format!("column `{full_name}` of `{ty:?}` has no Rust type");
+                        std::io::Error::new(std::io::ErrorKind::Unsupported, message)
+                    })?
+                }
+                FieldType::Object(nested) => {
+                    let nested_name = format!("{name}{}", pascal_case(&field.name));
+                    self.push_struct(nested, &nested_name, &full_name)?
+                }
+            };
+            let ty = match field.nullable {
+                true => format!("Option<{ty}>"),
+                false => ty,
+            };
+            let ident = unique(snake_case(&field.name), &mut idents);
+            if ident != field.name {
+                body.push_str(&format!("    #[hdv(rename = {:?})]\n", field.name));
+            }
+            body.push_str(&format!("    pub {ident}: {ty},\n"));
+        }
+        self.structs[index] = format!(
+            "#[derive(Debug, Clone, PartialEq, hdv_derive::HdvSerde)]\npub struct {name} {{\n{body}}}\n"
+        );
+        Ok(name)
+    }
+}
+
+/// Return [`None`] if no type of [`TemporalTypes`] or of the supported crates matches.
+fn rust_type(ty: &AtomType, temporal_types: TemporalTypes) -> Option<String> {
+    Some(
+        match ty {
+            AtomType::String => "String",
+            AtomType::Bytes => "Vec<u8>",
+            AtomType::U8 => "u8",
+            AtomType::U16 => "u16",
+            AtomType::U32 => "u32",
+            AtomType::U64 => "u64",
+            AtomType::U128 => "u128",
+            AtomType::I8 => "i8",
+            AtomType::I16 => "i16",
+            AtomType::I32 => "i32",
+            AtomType::I64 => "i64",
+            AtomType::I128 => "i128",
+            AtomType::F32 => "f32",
+            AtomType::F64 => "f64",
+            AtomType::Bool => "bool",
+            AtomType::List(item) => return Some(format!("Vec<{}>", item_type(*item))),
+            AtomType::Map(key, value) => {
+                // Floats are neither `Ord` nor `Hash`
+                if matches!(key, ItemType::F32 | ItemType::F64) {
+                    return None;
+                }
+                return Some(format!(
+                    "std::collections::BTreeMap<{}, {}>",
+                    item_type(*key),
+                    item_type(*value)
+                ));
+            }
+            AtomType::Timestamp { tz, .. } => match (tz.as_deref(), temporal_types) {
+                (Some("UTC"), TemporalTypes::Std) => "std::time::SystemTime",
+                (Some("UTC"), TemporalTypes::Chrono) => "chrono::DateTime<chrono::Utc>",
+                (Some("UTC"), TemporalTypes::Time) => "time::OffsetDateTime",
+                (None, TemporalTypes::Chrono) => "chrono::NaiveDateTime",
+                (None, TemporalTypes::Time) => "time::PrimitiveDateTime",
+                _ => return None,
+            },
+            AtomType::Date => match temporal_types {
+                TemporalTypes::Std => return None,
+                TemporalTypes::Chrono => "chrono::NaiveDate",
+                TemporalTypes::Time => "time::Date",
+            },
+            AtomType::Duration { .. } => match temporal_types {
+                TemporalTypes::Std => "std::time::Duration",
+                TemporalTypes::Chrono => "chrono::TimeDelta",
+                TemporalTypes::Time => "time::Duration",
+            },
+            AtomType::Uuid => "uuid::Uuid",
+            AtomType::Decimal { .. } => "rust_decimal::Decimal",
+        }
+        .to_string(),
+    )
+}
+fn item_type(ty: ItemType) -> &'static str {
+    match ty {
+        ItemType::String => "String",
+        ItemType::Bytes => "Vec<u8>",
+        ItemType::U64 => "u64",
+        ItemType::I64 => "i64",
+        ItemType::F32 => "f32",
+        ItemType::F64 => "f64",
+        ItemType::Bool => "bool",
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "match", "mod",
+    "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+    "true", "try", "type", "unsafe", "use", "where", "while", "yield",
+];
+
+/// Identifier of a field from a column name
+fn snake_case(name: &str) -> String {
+    let mut ident = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            if prev_lower {
+                ident.push('_');
+            }
+            ident.push(c.to_ascii_lowercase());
+        } else if c.is_ascii_alphanumeric() {
+            ident.push(c);
+        } else {
+            ident.push('_');
+        }
+        prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+    }
+    if !ident.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        ident.insert(0, '_');
+    }
+    if ident == "_" || KEYWORDS.contains(&ident.as_str()) {
+        ident.push('_');
+    }
+    ident
+}
+/// Part of a struct name from a field name
+fn pascal_case(name: &str) -> String {
+    let mut part = String::new();
+    let mut upper = true;
+    for c in name.chars() {
+        if !c.is_ascii_alphanumeric() {
+            upper = true;
+            continue;
+        }
+        match upper {
+            true => part.push(c.to_ascii_uppercase()),
+            false => part.push(c),
+        }
+        upper = false;
+    }
+    part
+}
+/// Append a number to `name` until it is not in `names`.
+fn unique(name: String, names: &mut HashSet<String>) -> String {
+    let mut unique = name.clone();
+    let mut i = 1;
+    while !names.insert(unique.clone()) {
+        i += 1;
+        unique = format!("{name}{i}");
+    }
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_rust() {
+        let header = [
+            ("id", AtomType::U64),
+            ("type", AtomType::String),
+            ("0", AtomType::Bool),
+            ("contact", AtomType::Bool),
+            ("contact.email", AtomType::String),
+            ("contact.phoneNumber", AtomType::U32),
+            ("tags", AtomType::List(ItemType::String)),
+            (
+                "created",
+                AtomType::Duration {
+                    unit: crate::format::TimeUnit::Milliseconds,
+                },
+            ),
+            ("pos.x", AtomType::F64),
+        ]
+        .map(|(name, r#type)| AtomScheme {
+            name: name.to_string(),
+            r#type,
+        });
+        let options = RustCodegenOptions {
+            root_name: "User".to_string(),
+            ..Default::default()
+        };
+        let code = generate_rust(&header, &options).unwrap();
+        assert_eq!(
+            code,
+            r#"// Generated from the header of a file by `hdv::codegen`
+
+#[derive(Debug, Clone, PartialEq, hdv_derive::HdvSerde)]
+pub struct User {
+    pub id: Option<u64>,
+    #[hdv(rename = "type")]
+    pub type_: Option<String>,
+    #[hdv(rename = "0")]
+    pub _0: Option<bool>,
+    pub contact: Option<UserContact>,
+    pub tags: Option<Vec<String>>,
+    pub created: Option<std::time::Duration>,
+    pub pos: UserPos,
+}
+
+#[derive(Debug, Clone, PartialEq, hdv_derive::HdvSerde)]
+pub struct UserContact {
+    pub email: Option<String>,
+    #[hdv(rename = "phoneNumber")]
+    pub phone_number: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, hdv_derive::HdvSerde)]
+pub struct UserPos {
+    pub x: Option<f64>,
+}
+"#
+        );
+
+        let header = [AtomScheme {
+            name: "day".to_string(),
+            r#type: AtomType::Date,
+        }];
+        let err = generate_rust(&header, &options).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+    }
+}
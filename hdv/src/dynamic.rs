@@ -0,0 +1,171 @@
+//! Records of object schemes only known at runtime
+
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    format::{AtomScheme, AtomValue},
+    serde::{impl_hdv_serialize_record, FieldType, HdvDeserializeSeed, HdvHeader, ObjectScheme},
+};
+
+/// Object scheme with its columns indexed by their full names
+///
+/// It is the seed of [`DynamicRecord`]s for the typed readers.
+#[derive(Debug, PartialEq)]
+pub struct DynamicScheme {
+    object_scheme: ObjectScheme,
+    header: Vec<AtomScheme>,
+    columns: HashMap<String, usize>,
+    /// Validity columns of the nullable objects enclosing each column
+    validities: Vec<Vec<usize>>,
+    /// Validity columns of all nullable objects
+    objects: Vec<usize>,
+}
+impl DynamicScheme {
+    /// # Panic
+    ///
+    /// If two columns share the same name.
+    pub fn new(object_scheme: ObjectScheme) -> Self {
+        object_scheme.assert_unique_names();
+        let header = object_scheme.atom_schemes();
+        let columns = header
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (x.name.clone(), i))
+            .collect();
+        let mut validities = vec![];
+        let mut objects = vec![];
+        push_validities(&object_scheme, &mut vec![], &mut validities, &mut objects);
+        Self {
+            object_scheme,
+            header,
+            columns,
+            validities,
+            objects,
+        }
+    }
+
+    pub fn object_scheme(&self) -> &ObjectScheme {
+        &self.object_scheme
+    }
+    pub fn header(&self) -> &[AtomScheme] {
+        &self.header
+    }
+
+    fn column(&self, name: &str) -> std::io::Result<usize> {
+        self.columns.get(name).copied().ok_or_else(|| {
+            let message = format!("column `{name}` is not in the scheme");
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
+        })
+    }
+}
+/// Collect the validity columns enclosing each column in the order of [`ObjectScheme::atom_schemes`]
+fn push_validities(
+    object: &ObjectScheme,
+    enclosing: &mut Vec<usize>,
+    validities: &mut Vec<Vec<usize>>,
+    objects: &mut Vec<usize>,
+) {
+    for field in &object.fields {
+        match &field.ty {
+            FieldType::Atom(_) => validities.push(enclosing.clone()),
+            FieldType::Object(nested) => {
+                if field.nullable {
+                    validities.push(enclosing.clone());
+                    enclosing.push(validities.len() - 1);
+                    objects.push(validities.len() - 1);
+                }
+                push_validities(nested, enclosing, validities, objects);
+                if field.nullable {
+                    enclosing.pop();
+                }
+            }
+        }
+    }
+}
+impl HdvDeserializeSeed for Arc<DynamicScheme> {
+    type Value = DynamicRecord;
+    fn object_scheme(&self) -> ObjectScheme {
+        self.object_scheme.clone()
+    }
+    fn deserialize(&self, values: &mut &[Option<AtomValue>]) -> Option<DynamicRecord> {
+        let len = self.header.len();
+        let record = values.get(..len)?.to_vec();
+        *values = &values[len..];
+        Some(DynamicRecord {
+            scheme: Arc::clone(self),
+            values: record,
+        })
+    }
+}
+
+/// Values of the columns of a shared [`DynamicScheme`] looked up by their full names, like `a.b`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicRecord {
+    scheme: Arc<DynamicScheme>,
+    values: Vec<Option<AtomValue>>,
+}
+impl DynamicRecord {
+    /// All values are null and all nullable objects are absent.
+    pub fn new(scheme: Arc<DynamicScheme>) -> Self {
+        let mut values = vec![None; scheme.header.len()];
+        for &object in &scheme.objects {
+            if scheme.validities[object].is_empty() {
+                values[object] = Some(AtomValue::Bool(false));
+            }
+        }
+        Self { scheme, values }
+    }
+
+    pub fn scheme(&self) -> &Arc<DynamicScheme> {
+        &self.scheme
+    }
+    pub fn values(&self) -> &[Option<AtomValue>] {
+        &self.values
+    }
+
+    pub fn get(&self, name: &str) -> std::io::Result<Option<&AtomValue>> {
+        let i = self.scheme.column(name)?;
+        Ok(self.values[i].as_ref())
+    }
+
+    /// A non-null value marks the nullable objects enclosing the column as present.
+    pub fn set(&mut self, name: &str, value: Option<AtomValue>) -> std::io::Result<()> {
+        let i = self.scheme.column(name)?;
+        if let Some(value) = &value {
+            let ty = &self.scheme.header[i].r#type;
+            if !ty.is_type_of(value) {
+                let message = format!("column `{name}`: value `{value:?}` is not of `{ty:?}`");
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    message,
+                ));
+            }
+            for validity in self.scheme.validities[i].clone() {
+                self.mark_present(validity);
+            }
+        }
+        self.values[i] = value;
+        Ok(())
+    }
+
+    /// Mark a nullable object as present and its directly nested nullable objects as absent
+    fn mark_present(&mut self, validity: usize) {
+        if self.values[validity] == Some(AtomValue::Bool(true)) {
+            return;
+        }
+        self.values[validity] = Some(AtomValue::Bool(true));
+        for &object in &self.scheme.objects {
+            if self.scheme.validities[object].last() == Some(&validity)
+                && self.values[object].is_none()
+            {
+                self.values[object] = Some(AtomValue::Bool(false));
+            }
+        }
+    }
+}
+impl HdvHeader for DynamicRecord {
+    fn header(&self) -> Vec<AtomScheme> {
+        self.scheme.header.clone()
+    }
+}
+impl_hdv_serialize_record!(DynamicRecord);
@@ -0,0 +1,388 @@
+//! Schemas of other formats for data catalogues
+//!
+//! Headers of files are exported through [`ObjectScheme::from_atom_schemes`].
+
+use crate::{
+    format::{AtomType, TimeUnit},
+    serde::{FieldType, ObjectScheme},
+};
+
+/// JSON Schema of an object whose fields are named as in the nested scheme
+///
+/// Nullable fields accept `null` and others are required.
+/// Bytes are in base64, decimals are strings and temporal values are in ISO-8601 like the text format.
+#[cfg(feature = "json")]
+pub fn json_schema(object: &ObjectScheme) -> serde_json::Value {
+    let mut schema = json_object(object);
+    schema["$schema"] = "https://json-schema.org/draft/2020-12/schema".into();
+    schema
+}
+#[cfg(feature = "json")]
+fn json_object(object: &ObjectScheme) -> serde_json::Value {
+    let mut properties = serde_json::Map::new();
+    let mut required = vec![];
+    push_json_properties(object, &mut properties, &mut required);
+    serde_json::json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+#[cfg(feature = "json")]
+fn push_json_properties(
+    object: &ObjectScheme,
+    properties: &mut serde_json::Map<String, serde_json::Value>,
+    required: &mut Vec<String>,
+) {
+    for field in &object.fields {
+        let schema = match &field.ty {
+            FieldType::Object(nested) if field.flatten => {
+                push_json_properties(nested, properties, required);
+                continue;
+            }
+            FieldType::Object(nested) => json_object(nested),
+            FieldType::Atom(ty) => json_atom(ty),
+        };
+        let schema = match field.nullable {
+            true => serde_json::json!({ "anyOf": [schema, { "type": "null" }] }),
+            false => {
+                required.push(field.name.clone());
+                schema
+            }
+        };
+        properties.insert(field.name.clone(), schema);
+    }
+}
+#[cfg(feature = "json")]
+fn json_atom(ty: &AtomType) -> serde_json::Value {
+    match ty {
+        AtomType::U8 => json_integer(0, u8::MAX),
+        AtomType::U16 => json_integer(0, u16::MAX),
+        AtomType::U32 => json_integer(0, u32::MAX),
+        AtomType::U64 => json_integer(0, u64::MAX),
+        AtomType::I8 => json_integer(i8::MIN, i8::MAX),
+        AtomType::I16 => json_integer(i16::MIN, i16::MAX),
+        AtomType::I32 => json_integer(i32::MIN, i32::MAX),
+        AtomType::I64 => json_integer(i64::MIN, i64::MAX),
+        AtomType::String => serde_json::json!({ "type": "string" }),
+        AtomType::Bytes => serde_json::json!({ "type": "string", "contentEncoding": "base64" }),
+        AtomType::U128 => serde_json::json!({ "type": "integer", "minimum": 0 }),
+        AtomType::I128 => serde_json::json!({ "type": "integer" }),
+        AtomType::F32 | AtomType::F64 => serde_json::json!({ "type": "number" }),
+        AtomType::Bool => serde_json::json!({ "type": "boolean" }),
+        AtomType::List(item) => serde_json::json!({
+            "type": "array",
+            "items": json_atom(&item.atom_type()),
+        }),
+        // Keys are strings in JSON
+        AtomType::Map(_, value) => serde_json::json!({
+            "type": "object",
+            "additionalProperties": json_atom(&value.atom_type()),
+        }),
+        AtomType::Timestamp { .. } => {
+            serde_json::json!({ "type": "string", "format": "date-time" })
+        }
+        AtomType::Date => serde_json::json!({ "type": "string", "format": "date" }),
+        AtomType::Duration { .. } => serde_json::json!({ "type": "string", "format": "duration" }),
+        AtomType::Uuid => serde_json::json!({ "type": "string", "format": "uuid" }),
+        AtomType::Decimal { .. } => {
+            serde_json::json!({ "type": "string", "pattern": "^-?[0-9]+(\\.[0-9]+)?$" })
+        }
+    }
+}
+#[cfg(feature = "json")]
+fn json_integer(
+    min: impl Into<serde_json::Value>,
+    max: impl Into<serde_json::Value>,
+) -> serde_json::Value {
+    serde_json::json!({ "type": "integer", "minimum": min.into(), "maximum": max.into() })
+}
+
+/// Arrow schema with object fields as `Struct` fields
+///
+/// Validity columns are the validity of the nullable `Struct` fields.
+/// UUIDs are 16-byte fixed size binaries.
+///
+/// Return an error of [`std::io::ErrorKind::Unsupported`] for 128-bit integers.
+#[cfg(feature = "arrow")]
+pub fn arrow_schema(object: &ObjectScheme) -> std::io::Result<arrow_schema::Schema> {
+    Ok(arrow_schema::Schema::new(arrow_fields(object)?))
+}
+#[cfg(feature = "arrow")]
+fn arrow_fields(object: &ObjectScheme) -> std::io::Result<Vec<arrow_schema::Field>> {
+    let mut fields = vec![];
+    for field in &object.fields {
+        let data_type = match &field.ty {
+            FieldType::Object(nested) if field.flatten => {
+                fields.extend(arrow_fields(nested)?);
+                continue;
+            }
+            FieldType::Object(nested) => {
+                arrow_schema::DataType::Struct(arrow_fields(nested)?.into())
+            }
+            FieldType::Atom(ty) => arrow_type(ty).ok_or_else(|| {
+                let message = format!("field `{}` of `{ty:?}` has no Arrow type", field.name);
+                std::io::Error::new(std::io::ErrorKind::Unsupported, message)
+            })?,
+        };
+        fields.push(arrow_schema::Field::new(
+            &field.name,
+            data_type,
+            field.nullable,
+        ));
+    }
+    Ok(fields)
+}
+#[cfg(feature = "arrow")]
+fn arrow_type(ty: &AtomType) -> Option<arrow_schema::DataType> {
+    use arrow_schema::DataType;
+    let time_unit = |unit: &TimeUnit| match unit {
+        TimeUnit::Milliseconds => arrow_schema::TimeUnit::Millisecond,
+        TimeUnit::Microseconds => arrow_schema::TimeUnit::Microsecond,
+        TimeUnit::Nanoseconds => arrow_schema::TimeUnit::Nanosecond,
+    };
+    let item = |item: &crate::format::ItemType| arrow_type(&item.atom_type()).unwrap();
+    Some(match ty {
+        AtomType::String => DataType::Utf8,
+        AtomType::Bytes => DataType::Binary,
+        AtomType::U8 => DataType::UInt8,
+        AtomType::U16 => DataType::UInt16,
+        AtomType::U32 => DataType::UInt32,
+        AtomType::U64 => DataType::UInt64,
+        AtomType::I8 => DataType::Int8,
+        AtomType::I16 => DataType::Int16,
+        AtomType::I32 => DataType::Int32,
+        AtomType::I64 => DataType::Int64,
+        AtomType::F32 => DataType::Float32,
+        AtomType::F64 => DataType::Float64,
+        AtomType::Bool => DataType::Boolean,
+        AtomType::List(x) => DataType::new_list(item(x), true),
+        AtomType::Map(key, value) => {
+            let entries = arrow_schema::Fields::from(vec![
+                arrow_schema::Field::new("key", item(key), false),
+                arrow_schema::Field::new("value", item(value), true),
+            ]);
+            let entries = arrow_schema::Field::new("entries", DataType::Struct(entries), false);
+            DataType::Map(entries.into(), false)
+        }
+        AtomType::Timestamp { unit, tz } => DataType::Timestamp(time_unit(unit), tz.clone()),
+        AtomType::Date => DataType::Date32,
+        AtomType::Duration { unit } => DataType::Duration(time_unit(unit)),
+        AtomType::Uuid => DataType::FixedSizeBinary(16),
+        AtomType::Decimal { precision, scale } => {
+            DataType::Decimal128(*precision, (*scale).try_into().ok()?)
+        }
+        AtomType::U128 | AtomType::I128 => return None,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlDialect {
+    Sqlite,
+    Postgres,
+}
+
+/// `CREATE TABLE` statement with a column of each atom field named by its full name
+///
+/// A column is `NOT NULL` if its field and the object fields enclosing it are not nullable.
+/// Temporal columns are integers in their units in SQLite.
+/// Lists and maps are `TEXT` in SQLite and maps are `JSONB` in Postgres.
+pub fn sql_create_table(object: &ObjectScheme, table: &str, dialect: SqlDialect) -> String {
+    let mut not_nulls = vec![];
+    push_not_nulls(object, true, &mut not_nulls);
+    let columns = object
+        .atom_schemes()
+        .iter()
+        .zip(not_nulls)
+        .map(|(atom, not_null)| {
+            let ty = sql_type(&atom.r#type, dialect);
+            let not_null = if not_null { " NOT NULL" } else { "" };
+            format!("    {} {ty}{not_null}", sql_ident(&atom.name))
+        })
+        .collect::<Vec<_>>();
+    format!(
+        "CREATE TABLE {} (\n{}\n);\n",
+        sql_ident(table),
+        columns.join(",\n")
+    )
+}
+/// Collect whether each column in the order of [`ObjectScheme::atom_schemes`] is not nullable.
+fn push_not_nulls(object: &ObjectScheme, not_null: bool, not_nulls: &mut Vec<bool>) {
+    for field in &object.fields {
+        let not_null = not_null && !field.nullable;
+        match &field.ty {
+            FieldType::Atom(_) => not_nulls.push(not_null),
+            FieldType::Object(nested) => {
+                if field.nullable {
+                    not_nulls.push(false);
+                }
+                push_not_nulls(nested, not_null, not_nulls);
+            }
+        }
+    }
+}
+fn sql_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+fn sql_type(ty: &AtomType, dialect: SqlDialect) -> String {
+    match dialect {
+        SqlDialect::Sqlite => match ty {
+            AtomType::String | AtomType::List(_) | AtomType::Map(..) => "TEXT",
+            AtomType::Bytes | AtomType::Uuid => "BLOB",
+            AtomType::F32 | AtomType::F64 => "REAL",
+            // Beyond the range of `INTEGER`
+            AtomType::U128 | AtomType::I128 | AtomType::Decimal { .. } => "TEXT",
+            AtomType::U8
+            | AtomType::U16
+            | AtomType::U32
+            | AtomType::U64
+            | AtomType::I8
+            | AtomType::I16
+            | AtomType::I32
+            | AtomType::I64
+            | AtomType::Bool
+            | AtomType::Timestamp { .. }
+            | AtomType::Date
+            | AtomType::Duration { .. } => "INTEGER",
+        }
+        .to_string(),
+        SqlDialect::Postgres => match ty {
+            AtomType::List(item) => format!("{}[]", sql_type(&item.atom_type(), dialect)),
+            AtomType::Decimal { precision, scale } => format!("NUMERIC({precision}, {scale})"),
+            AtomType::Timestamp { unit, tz } => {
+                let precision = match unit {
+                    TimeUnit::Milliseconds => "(3)",
+                    TimeUnit::Microseconds | TimeUnit::Nanoseconds => "",
+                };
+                match tz {
+                    Some(_) => format!("TIMESTAMPTZ{precision}"),
+                    None => format!("TIMESTAMP{precision}"),
+                }
+            }
+            AtomType::String => "TEXT".to_string(),
+            AtomType::Bytes => "BYTEA".to_string(),
+            AtomType::U8 | AtomType::I8 | AtomType::I16 => "SMALLINT".to_string(),
+            AtomType::U16 | AtomType::I32 => "INTEGER".to_string(),
+            AtomType::U32 | AtomType::I64 => "BIGINT".to_string(),
+            AtomType::U64 => "NUMERIC(20, 0)".to_string(),
+            AtomType::U128 | AtomType::I128 => "NUMERIC(39, 0)".to_string(),
+            AtomType::F32 => "REAL".to_string(),
+            AtomType::F64 => "DOUBLE PRECISION".to_string(),
+            AtomType::Bool => "BOOLEAN".to_string(),
+            AtomType::Map(..) => "JSONB".to_string(),
+            AtomType::Date => "DATE".to_string(),
+            AtomType::Duration { .. } => "INTERVAL".to_string(),
+            AtomType::Uuid => "UUID".to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format::{AtomScheme, ItemType};
+
+    use super::*;
+
+    fn object() -> ObjectScheme {
+        let header = [
+            ("id", AtomType::U32),
+            ("tags", AtomType::List(ItemType::String)),
+            ("contact", AtomType::Bool),
+            ("contact.email", AtomType::String),
+            ("pos.x", AtomType::F64),
+        ]
+        .map(|(name, r#type)| AtomScheme {
+            name: name.to_string(),
+            r#type,
+        });
+        let mut object = ObjectScheme::from_atom_schemes(&header).unwrap();
+        object.fields[0].nullable = false;
+        object
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_schema() {
+        assert_eq!(
+            json_schema(&object()),
+            serde_json::json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "object",
+                "properties": {
+                    "id": { "type": "integer", "minimum": 0, "maximum": u32::MAX },
+                    "tags": { "anyOf": [
+                        { "type": "array", "items": { "type": "string" } },
+                        { "type": "null" },
+                    ] },
+                    "contact": { "anyOf": [
+                        {
+                            "type": "object",
+                            "properties": {
+                                "email": { "anyOf": [{ "type": "string" }, { "type": "null" }] },
+                            },
+                            "required": [],
+                        },
+                        { "type": "null" },
+                    ] },
+                    "pos": {
+                        "type": "object",
+                        "properties": {
+                            "x": { "anyOf": [{ "type": "number" }, { "type": "null" }] },
+                        },
+                        "required": [],
+                    },
+                },
+                "required": ["id", "pos"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_sql_create_table() {
+        assert_eq!(
+            sql_create_table(&object(), "users", SqlDialect::Postgres),
+            r#"CREATE TABLE "users" (
+    "id" BIGINT NOT NULL,
+    "tags" TEXT[],
+    "contact" BOOLEAN,
+    "contact.email" TEXT,
+    "pos.x" DOUBLE PRECISION
+);
+"#
+        );
+        assert_eq!(
+            sql_create_table(&object(), "users", SqlDialect::Sqlite),
+            r#"CREATE TABLE "users" (
+    "id" INTEGER NOT NULL,
+    "tags" TEXT,
+    "contact" INTEGER,
+    "contact.email" TEXT,
+    "pos.x" REAL
+);
+"#
+        );
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn test_arrow_schema() {
+        use arrow_schema::{DataType, Field, Schema};
+
+        let contact = vec![Field::new("email", DataType::Utf8, true)];
+        let pos = vec![Field::new("x", DataType::Float64, true)];
+        assert_eq!(
+            arrow_schema(&object()).unwrap(),
+            Schema::new(vec![
+                Field::new("id", DataType::UInt32, false),
+                Field::new("tags", DataType::new_list(DataType::Utf8, true), true),
+                Field::new("contact", DataType::Struct(contact.into()), true),
+                Field::new("pos", DataType::Struct(pos.into()), false),
+            ])
+        );
+
+        let mut object = object();
+        object.fields[0].ty = FieldType::Atom(AtomType::U128);
+        let err = arrow_schema(&object).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+    }
+}
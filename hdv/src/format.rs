@@ -5,7 +5,6 @@ use std::{
 
 use integer_encoding::{FixedIntReader, FixedIntWriter, VarIntReader, VarIntWriter};
 use serde::{Deserialize, Serialize};
-use strum::EnumDiscriminants;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
 pub struct AtomScheme {
@@ -53,7 +52,7 @@ impl ValueRow {
     pub fn decode(atom_schemes: &[AtomScheme], buf: &mut std::io::Cursor<&[u8]>) -> Option<Self> {
         let mut atoms = vec![];
         let mut num_cont_somes: usize = 0;
-        for ty in atom_schemes.iter().map(|x| x.r#type) {
+        for ty in atom_schemes.iter().map(|x| &x.r#type) {
             if num_cont_somes == 0 {
                 num_cont_somes = buf.read_varint().ok()?;
             }
@@ -67,11 +66,239 @@ impl ValueRow {
         }
         Some(Self { atoms })
     }
+    /// Decode a row into values borrowing from `buf`.
+    pub fn decode_borrowed<'a>(
+        atom_schemes: &[AtomScheme],
+        mut buf: &'a [u8],
+        atoms: &mut Vec<Option<AtomValueRef<'a>>>,
+    ) -> Option<()> {
+        let mut num_cont_somes: usize = 0;
+        for ty in atom_schemes.iter().map(|x| &x.r#type) {
+            if num_cont_somes == 0 {
+                num_cont_somes = buf.read_varint().ok()?;
+            }
+            if num_cont_somes == 0 {
+                atoms.push(None);
+                continue;
+            }
+            let atom = AtomValueRef::decode(ty, &mut buf)?;
+            atoms.push(Some(atom));
+            num_cont_somes -= 1;
+        }
+        Some(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub enum AtomType {
+    String,
+    Bytes,
+    U64,
+    I64,
+    F32,
+    F64,
+    Bool,
+    List(ItemType),
+    /// Key type and value type
+    Map(ItemType, ItemType),
+    /// Time since the Unix epoch in UTC
+    ///
+    /// `tz` is the time zone the timestamps are presented in, such as `UTC` or `Europe/Paris`.
+    /// Timestamps without a time zone are wall clock times.
+    Timestamp {
+        unit: TimeUnit,
+        tz: Option<Arc<str>>,
+    },
+    /// Days since the Unix epoch
+    Date,
+    Duration {
+        unit: TimeUnit,
+    },
+    U128,
+    I128,
+    /// 16 bytes in RFC 4122 order
+    Uuid,
+    /// Exact decimal of at most `precision` digits, `scale` of which follow the decimal point
+    ///
+    /// `precision` is at most 38.
+    Decimal {
+        precision: u8,
+        scale: u8,
+    },
+    /// [`AtomValue::U64`] in the range of `u8`
+    U8,
+    /// [`AtomValue::U64`] in the range of `u16`
+    U16,
+    /// [`AtomValue::U64`] in the range of `u32`
+    U32,
+    /// [`AtomValue::I64`] in the range of `i8`
+    I8,
+    /// [`AtomValue::I64`] in the range of `i16`
+    I16,
+    /// [`AtomValue::I64`] in the range of `i32`
+    I32,
+}
+impl AtomType {
+    /// Return [`None`] if the type is not a list.
+    pub fn item_type(&self) -> Option<ItemType> {
+        let Self::List(x) = self else {
+            return None;
+        };
+        Some(*x)
+    }
+
+    /// Inclusive range of integer types of at most 64 bits
+    ///
+    /// Return [`None`] if the type is not such an integer.
+    pub fn int_range(&self) -> Option<(i128, i128)> {
+        Some(match self {
+            Self::U8 => (0, u8::MAX.into()),
+            Self::U16 => (0, u16::MAX.into()),
+            Self::U32 => (0, u32::MAX.into()),
+            Self::U64 => (0, u64::MAX.into()),
+            Self::I8 => (i8::MIN.into(), i8::MAX.into()),
+            Self::I16 => (i16::MIN.into(), i16::MAX.into()),
+            Self::I32 => (i32::MIN.into(), i32::MAX.into()),
+            Self::I64 => (i64::MIN.into(), i64::MAX.into()),
+            _ => return None,
+        })
+    }
+
+    /// Whether `value` is stored in columns of this type
+    ///
+    /// Timestamps and durations are in the unit of their columns.
+    pub fn is_type_of(&self, value: &AtomValue) -> bool {
+        match (self, value) {
+            (Self::U8 | Self::U16 | Self::U32, AtomValue::U64(x)) => {
+                let (_, max) = self.int_range().unwrap();
+                i128::from(*x) <= max
+            }
+            (Self::I8 | Self::I16 | Self::I32, AtomValue::I64(x)) => {
+                let (min, max) = self.int_range().unwrap();
+                (min..=max).contains(&i128::from(*x))
+            }
+            (Self::String, AtomValue::String(_))
+            | (Self::Bytes, AtomValue::Bytes(_))
+            | (Self::U64, AtomValue::U64(_))
+            | (Self::I64, AtomValue::I64(_))
+            | (Self::F32, AtomValue::F32(_))
+            | (Self::F64, AtomValue::F64(_))
+            | (Self::Bool, AtomValue::Bool(_))
+            | (Self::Timestamp { .. }, AtomValue::Timestamp(_))
+            | (Self::Date, AtomValue::Date(_))
+            | (Self::Duration { .. }, AtomValue::Duration(_))
+            | (Self::U128, AtomValue::U128(_))
+            | (Self::I128, AtomValue::I128(_))
+            | (Self::Uuid, AtomValue::Uuid(_)) => true,
+            (Self::Decimal { precision, .. }, AtomValue::Decimal(x)) => {
+                decimal_fits(*x, *precision)
+            }
+            (Self::List(a), AtomValue::List(b, _)) => a == b,
+            (Self::Map(a_k, a_v), AtomValue::Map(b_k, b_v, _)) => a_k == b_k && a_v == b_v,
+            _ => false,
+        }
+    }
+}
+
+/// Unit of timestamps and durations
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode,
+)]
+pub enum TimeUnit {
+    Milliseconds,
+    Microseconds,
+    Nanoseconds,
+}
+impl TimeUnit {
+    pub fn per_second(&self) -> i64 {
+        match self {
+            Self::Milliseconds => 1_000,
+            Self::Microseconds => 1_000_000,
+            Self::Nanoseconds => 1_000_000_000,
+        }
+    }
+
+    /// Digits of the fraction of a second
+    pub fn digits(&self) -> usize {
+        match self {
+            Self::Milliseconds => 3,
+            Self::Microseconds => 6,
+            Self::Nanoseconds => 9,
+        }
+    }
+
+    /// Convert `value` in this unit into `unit`.
+    ///
+    /// Return [`None`] on overflow or loss of precision.
+    pub fn convert(&self, value: i64, unit: TimeUnit) -> Option<i64> {
+        let (from, to) = (self.per_second(), unit.per_second());
+        if from <= to {
+            value.checked_mul(to / from)
+        } else {
+            let factor = from / to;
+            (value % factor == 0).then_some(value / factor)
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, EnumDiscriminants, bincode::Encode, bincode::Decode)]
-#[strum_discriminants(derive(Serialize, Deserialize, bincode::Encode, bincode::Decode))]
-#[strum_discriminants(name(AtomType))]
+/// Type of the items of a list or the keys and values of a map
+///
+/// Lists and maps do not nest.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode,
+)]
+pub enum ItemType {
+    String,
+    Bytes,
+    U64,
+    I64,
+    F32,
+    F64,
+    Bool,
+}
+impl ItemType {
+    pub fn atom_type(&self) -> AtomType {
+        match self {
+            Self::String => AtomType::String,
+            Self::Bytes => AtomType::Bytes,
+            Self::U64 => AtomType::U64,
+            Self::I64 => AtomType::I64,
+            Self::F32 => AtomType::F32,
+            Self::F64 => AtomType::F64,
+            Self::Bool => AtomType::Bool,
+        }
+    }
+
+    /// Return [`None`] if the type is not of an item.
+    pub fn from_atom_type(atom_type: &AtomType) -> Option<Self> {
+        Some(match atom_type {
+            AtomType::String => Self::String,
+            AtomType::Bytes => Self::Bytes,
+            AtomType::U64 => Self::U64,
+            AtomType::I64 => Self::I64,
+            AtomType::F32 => Self::F32,
+            AtomType::F64 => Self::F64,
+            AtomType::Bool => Self::Bool,
+            AtomType::List(_)
+            | AtomType::Map(..)
+            | AtomType::Timestamp { .. }
+            | AtomType::Date
+            | AtomType::Duration { .. }
+            | AtomType::U128
+            | AtomType::I128
+            | AtomType::Uuid
+            | AtomType::Decimal { .. }
+            | AtomType::U8
+            | AtomType::U16
+            | AtomType::U32
+            | AtomType::I8
+            | AtomType::I16
+            | AtomType::I32 => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, bincode::Encode, bincode::Decode)]
 pub enum AtomValue {
     String(Arc<str>),
     Bytes(Arc<[u8]>),
@@ -80,6 +307,21 @@ pub enum AtomValue {
     F32(f32),
     F64(f64),
     Bool(bool),
+    /// Non-null items all of the item type
+    List(ItemType, Arc<[AtomValue]>),
+    /// Entries of non-null keys and values of the key type and the value type
+    Map(ItemType, ItemType, Arc<[(AtomValue, AtomValue)]>),
+    /// In the unit of its column
+    Timestamp(i64),
+    /// Days since the Unix epoch
+    Date(i32),
+    /// In the unit of its column
+    Duration(i64),
+    U128(u128),
+    I128(i128),
+    Uuid([u8; 16]),
+    /// Mantissa in the scale of its column
+    Decimal(i128),
 }
 impl AtomValue {
     pub fn string(&self) -> Option<&Arc<str>> {
@@ -124,6 +366,60 @@ impl AtomValue {
         };
         Some(*x)
     }
+    pub fn list(&self) -> Option<&Arc<[AtomValue]>> {
+        let Self::List(_, x) = self else {
+            return None;
+        };
+        Some(x)
+    }
+    pub fn map(&self) -> Option<&Arc<[(AtomValue, AtomValue)]>> {
+        let Self::Map(_, _, x) = self else {
+            return None;
+        };
+        Some(x)
+    }
+    pub fn timestamp(&self) -> Option<i64> {
+        let Self::Timestamp(x) = self else {
+            return None;
+        };
+        Some(*x)
+    }
+    pub fn date(&self) -> Option<i32> {
+        let Self::Date(x) = self else {
+            return None;
+        };
+        Some(*x)
+    }
+    pub fn duration(&self) -> Option<i64> {
+        let Self::Duration(x) = self else {
+            return None;
+        };
+        Some(*x)
+    }
+    pub fn u128(&self) -> Option<u128> {
+        let Self::U128(x) = self else {
+            return None;
+        };
+        Some(*x)
+    }
+    pub fn i128(&self) -> Option<i128> {
+        let Self::I128(x) = self else {
+            return None;
+        };
+        Some(*x)
+    }
+    pub fn uuid(&self) -> Option<[u8; 16]> {
+        let Self::Uuid(x) = self else {
+            return None;
+        };
+        Some(*x)
+    }
+    pub fn decimal(&self) -> Option<i128> {
+        let Self::Decimal(x) = self else {
+            return None;
+        };
+        Some(*x)
+    }
 
     const BOOL_FALSE: u8 = 0;
     const BOOL_TRUE: u8 = 1;
@@ -159,11 +455,39 @@ impl AtomValue {
                 })
                 .unwrap();
             }
+            AtomValue::List(_, x) => {
+                buf.write_varint(x.len()).unwrap();
+                for item in x.iter() {
+                    item.encode(buf);
+                }
+            }
+            AtomValue::Map(_, _, x) => {
+                buf.write_varint(x.len()).unwrap();
+                for (key, value) in x.iter() {
+                    key.encode(buf);
+                    value.encode(buf);
+                }
+            }
+            AtomValue::Timestamp(x) | AtomValue::Duration(x) => {
+                buf.write_varint(*x).unwrap();
+            }
+            AtomValue::Date(x) => {
+                buf.write_varint(*x).unwrap();
+            }
+            AtomValue::U128(x) => {
+                write_varint_u128(buf, *x);
+            }
+            AtomValue::I128(x) | AtomValue::Decimal(x) => {
+                write_varint_i128(buf, *x);
+            }
+            AtomValue::Uuid(x) => {
+                buf.write_all(x).unwrap();
+            }
         }
     }
 
-    pub fn decode(ty: AtomType, buf: &mut std::io::Cursor<&[u8]>) -> Option<Self> {
-        match ty {
+    pub fn decode(ty: &AtomType, buf: &mut std::io::Cursor<&[u8]>) -> Option<Self> {
+        match *ty {
             AtomType::String => {
                 let len: usize = buf.read_varint().ok()?;
                 let mut bytes = vec![0; len];
@@ -184,6 +508,14 @@ impl AtomValue {
                 let x: i64 = buf.read_varint().ok()?;
                 Some(Self::I64(x))
             }
+            AtomType::U8 | AtomType::U16 | AtomType::U32 => {
+                let x: u64 = buf.read_varint().ok()?;
+                Some(Self::U64(x)).filter(|x| ty.is_type_of(x))
+            }
+            AtomType::I8 | AtomType::I16 | AtomType::I32 => {
+                let x: i64 = buf.read_varint().ok()?;
+                Some(Self::I64(x)).filter(|x| ty.is_type_of(x))
+            }
             AtomType::F32 => {
                 let bits: u32 = buf.read_fixedint().ok()?;
                 Some(Self::F32(f32::from_bits(bits)))
@@ -200,6 +532,458 @@ impl AtomValue {
                     _ => return None,
                 }))
             }
+            AtomType::List(item_type) => {
+                let len: usize = buf.read_varint().ok()?;
+                // Every item takes at least one byte
+                let remaining = buf.get_ref().len() as u64 - buf.position();
+                if remaining < len as u64 {
+                    return None;
+                }
+                let mut items = Vec::with_capacity(len);
+                for _ in 0..len {
+                    items.push(Self::decode(&item_type.atom_type(), buf)?);
+                }
+                Some(Self::List(item_type, items.into()))
+            }
+            AtomType::Map(key_type, value_type) => {
+                let len: usize = buf.read_varint().ok()?;
+                // Every entry takes at least two bytes
+                let remaining = buf.get_ref().len() as u64 - buf.position();
+                if remaining / 2 < len as u64 {
+                    return None;
+                }
+                let mut entries = Vec::with_capacity(len);
+                for _ in 0..len {
+                    let key = Self::decode(&key_type.atom_type(), buf)?;
+                    let value = Self::decode(&value_type.atom_type(), buf)?;
+                    entries.push((key, value));
+                }
+                Some(Self::Map(key_type, value_type, entries.into()))
+            }
+            AtomType::Timestamp { .. } => Some(Self::Timestamp(buf.read_varint().ok()?)),
+            AtomType::Date => Some(Self::Date(buf.read_varint().ok()?)),
+            AtomType::Duration { .. } => Some(Self::Duration(buf.read_varint().ok()?)),
+            AtomType::U128 => Some(Self::U128(read_varint_u128(buf)?)),
+            AtomType::I128 => Some(Self::I128(read_varint_i128(buf)?)),
+            AtomType::Decimal { .. } => Some(Self::Decimal(read_varint_i128(buf)?)),
+            AtomType::Uuid => {
+                let mut bytes = [0; 16];
+                buf.read_exact(&mut bytes).ok()?;
+                Some(Self::Uuid(bytes))
+            }
+        }
+    }
+}
+/// LEB128 as `integer_encoding` does for `u64`
+fn write_varint_u128(buf: &mut Vec<u8>, mut x: u128) {
+    while 0x80 <= x {
+        buf.push(x as u8 | 0x80);
+        x >>= 7;
+    }
+    buf.push(x as u8);
+}
+fn read_varint_u128<R: Read>(read: &mut R) -> Option<u128> {
+    let mut x: u128 = 0;
+    for i in 0..19 {
+        let mut byte = [0];
+        read.read_exact(&mut byte).ok()?;
+        let bits = u128::from(byte[0] & 0x7f);
+        // The 19th byte holds the top two bits
+        if i == 18 && 0b11 < bits {
+            return None;
+        }
+        x |= bits << (7 * i);
+        if byte[0] & 0x80 == 0 {
+            return Some(x);
+        }
+    }
+    None
+}
+/// Zigzag as `integer_encoding` does for `i64`
+fn write_varint_i128(buf: &mut Vec<u8>, x: i128) {
+    write_varint_u128(buf, ((x << 1) ^ (x >> 127)) as u128);
+}
+fn read_varint_i128<R: Read>(read: &mut R) -> Option<i128> {
+    let x = read_varint_u128(read)?;
+    Some((x >> 1) as i128 ^ -((x & 1) as i128))
+}
+/// Whether `mantissa` has at most `precision` digits
+fn decimal_fits(mantissa: i128, precision: u8) -> bool {
+    match 10_u128.checked_pow(precision.into()) {
+        Some(bound) => mantissa.unsigned_abs() < bound,
+        None => true,
+    }
+}
+
+/// [`AtomValue`] borrowing its string and bytes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AtomValueRef<'a> {
+    String(&'a str),
+    Bytes(&'a [u8]),
+    U64(u64),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+    Bool(bool),
+    List(ListRef<'a>),
+    Map(MapRef<'a>),
+    Timestamp(i64),
+    Date(i32),
+    Duration(i64),
+    U128(u128),
+    I128(i128),
+    Uuid([u8; 16]),
+    Decimal(i128),
+}
+impl<'a> AtomValueRef<'a> {
+    pub fn string(&self) -> Option<&'a str> {
+        let Self::String(x) = self else {
+            return None;
+        };
+        Some(x)
+    }
+    pub fn bytes(&self) -> Option<&'a [u8]> {
+        let Self::Bytes(x) = self else {
+            return None;
+        };
+        Some(x)
+    }
+    pub fn u64(&self) -> Option<u64> {
+        let Self::U64(x) = self else {
+            return None;
+        };
+        Some(*x)
+    }
+    pub fn i64(&self) -> Option<i64> {
+        let Self::I64(x) = self else {
+            return None;
+        };
+        Some(*x)
+    }
+    pub fn f32(&self) -> Option<f32> {
+        let Self::F32(x) = self else {
+            return None;
+        };
+        Some(*x)
+    }
+    pub fn f64(&self) -> Option<f64> {
+        let Self::F64(x) = self else {
+            return None;
+        };
+        Some(*x)
+    }
+    pub fn bool(&self) -> Option<bool> {
+        let Self::Bool(x) = self else {
+            return None;
+        };
+        Some(*x)
+    }
+    pub fn list(&self) -> Option<ListRef<'a>> {
+        let Self::List(x) = self else {
+            return None;
+        };
+        Some(*x)
+    }
+    pub fn map(&self) -> Option<MapRef<'a>> {
+        let Self::Map(x) = self else {
+            return None;
+        };
+        Some(*x)
+    }
+    pub fn timestamp(&self) -> Option<i64> {
+        let Self::Timestamp(x) = self else {
+            return None;
+        };
+        Some(*x)
+    }
+    pub fn date(&self) -> Option<i32> {
+        let Self::Date(x) = self else {
+            return None;
+        };
+        Some(*x)
+    }
+    pub fn duration(&self) -> Option<i64> {
+        let Self::Duration(x) = self else {
+            return None;
+        };
+        Some(*x)
+    }
+    pub fn u128(&self) -> Option<u128> {
+        let Self::U128(x) = self else {
+            return None;
+        };
+        Some(*x)
+    }
+    pub fn i128(&self) -> Option<i128> {
+        let Self::I128(x) = self else {
+            return None;
+        };
+        Some(*x)
+    }
+    pub fn uuid(&self) -> Option<[u8; 16]> {
+        let Self::Uuid(x) = self else {
+            return None;
+        };
+        Some(*x)
+    }
+    pub fn decimal(&self) -> Option<i128> {
+        let Self::Decimal(x) = self else {
+            return None;
+        };
+        Some(*x)
+    }
+
+    pub fn to_owned(&self) -> AtomValue {
+        match *self {
+            Self::String(x) => AtomValue::String(x.into()),
+            Self::Bytes(x) => AtomValue::Bytes(x.into()),
+            Self::U64(x) => AtomValue::U64(x),
+            Self::I64(x) => AtomValue::I64(x),
+            Self::F32(x) => AtomValue::F32(x),
+            Self::F64(x) => AtomValue::F64(x),
+            Self::Bool(x) => AtomValue::Bool(x),
+            Self::List(x) => {
+                AtomValue::List(x.item_type(), x.iter().map(|x| x.to_owned()).collect())
+            }
+            Self::Map(x) => AtomValue::Map(
+                x.key_type(),
+                x.value_type(),
+                x.iter()
+                    .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                    .collect(),
+            ),
+            Self::Timestamp(x) => AtomValue::Timestamp(x),
+            Self::Date(x) => AtomValue::Date(x),
+            Self::Duration(x) => AtomValue::Duration(x),
+            Self::U128(x) => AtomValue::U128(x),
+            Self::I128(x) => AtomValue::I128(x),
+            Self::Uuid(x) => AtomValue::Uuid(x),
+            Self::Decimal(x) => AtomValue::Decimal(x),
+        }
+    }
+
+    /// Return [`None`] if the value is a string, bytes, a list or a map.
+    pub fn from_scalar(value: AtomValue) -> Option<Self> {
+        Some(match value {
+            AtomValue::String(_)
+            | AtomValue::Bytes(_)
+            | AtomValue::List(..)
+            | AtomValue::Map(..) => return None,
+            AtomValue::U64(x) => Self::U64(x),
+            AtomValue::I64(x) => Self::I64(x),
+            AtomValue::F32(x) => Self::F32(x),
+            AtomValue::F64(x) => Self::F64(x),
+            AtomValue::Bool(x) => Self::Bool(x),
+            AtomValue::Timestamp(x) => Self::Timestamp(x),
+            AtomValue::Date(x) => Self::Date(x),
+            AtomValue::Duration(x) => Self::Duration(x),
+            AtomValue::U128(x) => Self::U128(x),
+            AtomValue::I128(x) => Self::I128(x),
+            AtomValue::Uuid(x) => Self::Uuid(x),
+            AtomValue::Decimal(x) => Self::Decimal(x),
+        })
+    }
+
+    pub fn decode(ty: &AtomType, buf: &mut &'a [u8]) -> Option<Self> {
+        Some(match *ty {
+            AtomType::String => {
+                let bytes = Self::decode_bytes(buf)?;
+                Self::String(std::str::from_utf8(bytes).ok()?)
+            }
+            AtomType::Bytes => Self::Bytes(Self::decode_bytes(buf)?),
+            AtomType::List(item_type) => {
+                let len: usize = buf.read_varint().ok()?;
+                let bytes = *buf;
+                for _ in 0..len {
+                    Self::decode(&item_type.atom_type(), buf)?;
+                }
+                let bytes = &bytes[..bytes.len() - buf.len()];
+                Self::List(ListRef {
+                    item_type,
+                    items: ListItems::Encoded { len, bytes },
+                })
+            }
+            AtomType::Map(key_type, value_type) => {
+                let len: usize = buf.read_varint().ok()?;
+                let bytes = *buf;
+                for _ in 0..len {
+                    Self::decode(&key_type.atom_type(), buf)?;
+                    Self::decode(&value_type.atom_type(), buf)?;
+                }
+                let bytes = &bytes[..bytes.len() - buf.len()];
+                Self::Map(MapRef {
+                    key_type,
+                    value_type,
+                    entries: MapEntries::Encoded { len, bytes },
+                })
+            }
+            _ => {
+                let mut cursor = std::io::Cursor::new(*buf);
+                let value = AtomValue::decode(ty, &mut cursor)?;
+                *buf = &buf[cursor.position() as usize..];
+                Self::from_scalar(value)?
+            }
+        })
+    }
+    fn decode_bytes(buf: &mut &'a [u8]) -> Option<&'a [u8]> {
+        let len: usize = buf.read_varint().ok()?;
+        if buf.len() < len {
+            return None;
+        }
+        let (bytes, rest) = buf.split_at(len);
+        *buf = rest;
+        Some(bytes)
+    }
+}
+impl<'a> From<&'a AtomValue> for AtomValueRef<'a> {
+    fn from(value: &'a AtomValue) -> Self {
+        match value {
+            AtomValue::String(x) => Self::String(x),
+            AtomValue::Bytes(x) => Self::Bytes(x),
+            AtomValue::U64(x) => Self::U64(*x),
+            AtomValue::I64(x) => Self::I64(*x),
+            AtomValue::F32(x) => Self::F32(*x),
+            AtomValue::F64(x) => Self::F64(*x),
+            AtomValue::Bool(x) => Self::Bool(*x),
+            AtomValue::Timestamp(x) => Self::Timestamp(*x),
+            AtomValue::Date(x) => Self::Date(*x),
+            AtomValue::Duration(x) => Self::Duration(*x),
+            AtomValue::U128(x) => Self::U128(*x),
+            AtomValue::I128(x) => Self::I128(*x),
+            AtomValue::Uuid(x) => Self::Uuid(*x),
+            AtomValue::Decimal(x) => Self::Decimal(*x),
+            AtomValue::List(item_type, x) => Self::List(ListRef {
+                item_type: *item_type,
+                items: ListItems::Decoded(x),
+            }),
+            AtomValue::Map(key_type, value_type, x) => Self::Map(MapRef {
+                key_type: *key_type,
+                value_type: *value_type,
+                entries: MapEntries::Decoded(x),
+            }),
+        }
+    }
+}
+
+/// List borrowing its items
+#[derive(Debug, Clone, Copy)]
+pub struct ListRef<'a> {
+    item_type: ItemType,
+    items: ListItems<'a>,
+}
+#[derive(Debug, Clone, Copy)]
+enum ListItems<'a> {
+    /// Validated items still in the row buffer
+    Encoded {
+        len: usize,
+        bytes: &'a [u8],
+    },
+    Decoded(&'a [AtomValue]),
+}
+impl<'a> ListRef<'a> {
+    pub fn item_type(&self) -> ItemType {
+        self.item_type
+    }
+
+    pub fn len(&self) -> usize {
+        match self.items {
+            ListItems::Encoded { len, .. } => len,
+            ListItems::Decoded(x) => x.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = AtomValueRef<'a>> + 'a {
+        let item_type = self.item_type.atom_type();
+        let (encoded, decoded) = match self.items {
+            ListItems::Encoded { len, mut bytes } => {
+                let items =
+                    (0..len).map(move |_| AtomValueRef::decode(&item_type, &mut bytes).unwrap());
+                (Some(items), None)
+            }
+            ListItems::Decoded(x) => (None, Some(x.iter().map(AtomValueRef::from))),
+        };
+        encoded
+            .into_iter()
+            .flatten()
+            .chain(decoded.into_iter().flatten())
+    }
+}
+impl PartialEq for ListRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.item_type == other.item_type && self.iter().eq(other.iter())
+    }
+}
+
+/// Map borrowing its entries
+#[derive(Debug, Clone, Copy)]
+pub struct MapRef<'a> {
+    key_type: ItemType,
+    value_type: ItemType,
+    entries: MapEntries<'a>,
+}
+#[derive(Debug, Clone, Copy)]
+enum MapEntries<'a> {
+    /// Validated entries still in the row buffer
+    Encoded {
+        len: usize,
+        bytes: &'a [u8],
+    },
+    Decoded(&'a [(AtomValue, AtomValue)]),
+}
+impl<'a> MapRef<'a> {
+    pub fn key_type(&self) -> ItemType {
+        self.key_type
+    }
+
+    pub fn value_type(&self) -> ItemType {
+        self.value_type
+    }
+
+    pub fn len(&self) -> usize {
+        match self.entries {
+            MapEntries::Encoded { len, .. } => len,
+            MapEntries::Decoded(x) => x.len(),
         }
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (AtomValueRef<'a>, AtomValueRef<'a>)> + 'a {
+        let key_type = self.key_type.atom_type();
+        let value_type = self.value_type.atom_type();
+        let (encoded, decoded) = match self.entries {
+            MapEntries::Encoded { len, mut bytes } => {
+                let entries = (0..len).map(move |_| {
+                    let key = AtomValueRef::decode(&key_type, &mut bytes).unwrap();
+                    let value = AtomValueRef::decode(&value_type, &mut bytes).unwrap();
+                    (key, value)
+                });
+                (Some(entries), None)
+            }
+            MapEntries::Decoded(x) => (
+                None,
+                Some(
+                    x.iter()
+                        .map(|(k, v)| (AtomValueRef::from(k), AtomValueRef::from(v))),
+                ),
+            ),
+        };
+        encoded
+            .into_iter()
+            .flatten()
+            .chain(decoded.into_iter().flatten())
+    }
+}
+impl PartialEq for MapRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key_type == other.key_type
+            && self.value_type == other.value_type
+            && self.iter().eq(other.iter())
+    }
 }
@@ -3,15 +3,18 @@ use std::marker::PhantomData;
 use integer_encoding::{VarIntReader, VarIntWriter};
 
 use crate::{
-    format::{AtomScheme, AtomValue, ValueRow},
-    serde::{HdvDeserialize, HdvScheme, HdvSerialize},
+    format::{AtomScheme, AtomValue, AtomValueRef, ValueRow},
+    serde::{
+        HdvDeserialize, HdvDeserializeBorrowed, HdvDeserializeSeed, HdvHeader, HdvScheme,
+        HdvSerializeRow, ObjectScheme,
+    },
 };
 
-use super::{assert_atom_types, HdvShiftedHeader};
+use super::{check_atom_types, HdvReaderOptions, HdvShiftedHeader};
 
 #[derive(Debug)]
 pub struct HdvBinWriter<W, O> {
-    has_written_header: bool,
+    header: Option<Vec<AtomScheme>>,
     write: W,
     buf: Vec<u8>,
     _object: PhantomData<O>,
@@ -19,7 +22,7 @@ pub struct HdvBinWriter<W, O> {
 impl<W, O> HdvBinWriter<W, O> {
     pub fn new(write: W) -> Self {
         Self {
-            has_written_header: false,
+            header: None,
             write,
             buf: vec![],
             _object: PhantomData,
@@ -29,21 +32,23 @@ impl<W, O> HdvBinWriter<W, O> {
 impl<W, O> HdvBinWriter<W, O>
 where
     W: std::io::Write,
-    O: HdvSerialize + HdvScheme,
+    O: HdvSerializeRow + HdvHeader,
 {
     pub fn write(&mut self, object: &O) -> std::io::Result<()> {
-        if !self.has_written_header {
-            self.has_written_header = true;
-
-            let header = O::object_scheme();
-            let header = header.atom_schemes();
-            write_header(&mut self.write, &header)?;
-        }
+        let header = match &self.header {
+            Some(header) => header,
+            None => {
+                let header = object.header();
+                write_header(&mut self.write, &header)?;
+                self.header.insert(header)
+            }
+        };
 
         let mut atoms = vec![];
-        object.serialize(&mut atoms);
+        object.serialize_row(&mut atoms);
 
         let row = ValueRow::new(atoms);
+        check_atom_types(header, &row)?;
         write_row(&mut self.write, &row, &mut self.buf)?;
         Ok(())
     }
@@ -81,7 +86,7 @@ where
             write_header(&mut self.write, &self.header)?;
         }
 
-        assert_atom_types(&self.header, row);
+        check_atom_types(&self.header, row)?;
 
         write_row(&mut self.write, row, &mut self.buf)?;
         Ok(())
@@ -94,19 +99,28 @@ where
 
 #[derive(Debug)]
 pub struct HdvBinReader<R, O> {
+    options: HdvReaderOptions,
     shift_header: Option<HdvShiftedHeader>,
     read: R,
     buf: Vec<u8>,
     atom_value_buf: Vec<Option<AtomValue>>,
+    /// Empty buffers of [`Self::read_borrowed`] kept for their capacities
+    atom_value_ref_bufs: [Vec<Option<AtomValueRef<'static>>>; 2],
     _object: PhantomData<O>,
 }
 impl<R, V> HdvBinReader<R, V> {
     pub fn new(read: R) -> Self {
+        Self::with_options(read, HdvReaderOptions::default())
+    }
+
+    pub fn with_options(read: R, options: HdvReaderOptions) -> Self {
         Self {
+            options,
             shift_header: None,
             read,
             buf: vec![],
             atom_value_buf: vec![],
+            atom_value_ref_bufs: [vec![], vec![]],
             _object: PhantomData,
         }
     }
@@ -114,26 +128,91 @@ impl<R, V> HdvBinReader<R, V> {
 impl<R, O> HdvBinReader<R, O>
 where
     R: std::io::Read,
-    O: HdvDeserialize + HdvScheme,
 {
-    pub fn read(&mut self) -> std::io::Result<O> {
-        let Some(shift_header) = &self.shift_header else {
-            let header = read_header(&mut self.read)?;
-            let shift_header = HdvShiftedHeader::new(header, &O::object_scheme())
-                .ok_or(std::io::ErrorKind::InvalidInput)?;
-            self.shift_header = Some(shift_header);
+    /// `is_borrowed` rejects the columns [`Self::read_borrowed`] cannot read.
+    fn init_shift_header(
+        &mut self,
+        object_scheme: impl FnOnce() -> ObjectScheme,
+        is_borrowed: bool,
+    ) -> std::io::Result<()> {
+        if self.shift_header.is_some() {
+            return Ok(());
+        }
+        let header = read_header(&mut self.read)?;
+        let shift_header = HdvShiftedHeader::new(header, &object_scheme(), &self.options)?;
+        if is_borrowed {
+            shift_header.check_borrowed()?;
+        }
+        self.shift_header = Some(shift_header);
+        Ok(())
+    }
 
-            return self.read();
-        };
+    /// Read an object of a scheme only known at runtime.
+    ///
+    /// The header is matched against the scheme of the seed of the first read.
+    pub fn read_seed<S>(&mut self, seed: &S) -> std::io::Result<O>
+    where
+        S: HdvDeserializeSeed<Value = O>,
+    {
+        self.init_shift_header(|| seed.object_scheme(), false)?;
+        let shift_header = self.shift_header.as_ref().unwrap();
 
         let row = read_row(&mut self.read, shift_header.header(), &mut self.buf)?;
         self.atom_value_buf.clear();
-        shift_header.shift(row.atoms(), &mut self.atom_value_buf);
+        shift_header.shift(row.atoms(), &mut self.atom_value_buf)?;
 
-        let object = O::deserialize(&mut self.atom_value_buf.as_slice()).unwrap();
+        let object = seed
+            .deserialize(&mut self.atom_value_buf.as_slice())
+            .ok_or(std::io::ErrorKind::InvalidData)?;
+        Ok(object)
+    }
+}
+impl<R, O> HdvBinReader<R, O>
+where
+    R: std::io::Read,
+    O: HdvDeserializeBorrowed + HdvScheme,
+{
+    /// Read an object borrowing from the row buffer of this reader.
+    ///
+    /// The object is valid until the next read.
+    /// For types with lifetimes, `O` is the type with `'static` lifetimes, like `View<'static>`.
+    /// Derived types without lifetimes implement [`HdvDeserializeBorrowed`] with `#[hdv(borrow)]`.
+    pub fn read_borrowed(&mut self) -> std::io::Result<O::Borrowed<'_>> {
+        self.init_shift_header(O::object_scheme, true)?;
+        let shift_header = self.shift_header.as_ref().unwrap();
+
+        read_row_bytes(&mut self.read, &mut self.buf)?;
+        // References into the row buffer only live until the next read,
+        // so the buffers of them are emptied after each read and only their capacities are kept
+        let [row, values] = &mut self.atom_value_ref_bufs;
+        let mut row = recycle_buf(std::mem::take(row));
+        let mut values = recycle_buf(std::mem::take(values));
+        ValueRow::decode_borrowed(shift_header.header(), &self.buf, &mut row)
+            .ok_or(std::io::ErrorKind::InvalidInput)?;
+        shift_header.shift_borrowed(&row, &mut values)?;
+
+        let object = O::deserialize_borrowed(&mut values.as_slice());
+        self.atom_value_ref_bufs = [recycle_buf(row), recycle_buf(values)];
+        let object = object.ok_or(std::io::ErrorKind::InvalidData)?;
         Ok(object)
     }
 }
+/// Empty `buf` and give it another lifetime, keeping its allocation
+///
+/// Collecting an emptied `Vec` into one of the same layout reuses its allocation.
+fn recycle_buf<'a, 'b>(mut buf: Vec<Option<AtomValueRef<'a>>>) -> Vec<Option<AtomValueRef<'b>>> {
+    buf.clear();
+    buf.into_iter().map(|_| unreachable!()).collect()
+}
+impl<R, O> HdvBinReader<R, O>
+where
+    R: std::io::Read,
+    O: HdvDeserialize + HdvScheme,
+{
+    pub fn read(&mut self) -> std::io::Result<O> {
+        self.read_seed(&PhantomData)
+    }
+}
 
 #[derive(Debug)]
 pub struct HdvBinRawReader<R> {
@@ -170,7 +249,7 @@ where
     }
 }
 
-fn write_header<W>(write: &mut W, header: &[AtomScheme]) -> std::io::Result<()>
+pub(super) fn write_header<W>(write: &mut W, header: &[AtomScheme]) -> std::io::Result<()>
 where
     W: std::io::Write,
 {
@@ -179,7 +258,7 @@ where
     write.write_all(&header)?;
     Ok(())
 }
-fn read_header<R>(read: &mut R) -> std::io::Result<Vec<AtomScheme>>
+pub(crate) fn read_header<R>(read: &mut R) -> std::io::Result<Vec<AtomScheme>>
 where
     R: std::io::Read,
 {
@@ -192,7 +271,7 @@ where
     Ok(header)
 }
 
-fn write_row<W>(write: &mut W, row: &ValueRow, buf: &mut Vec<u8>) -> std::io::Result<()>
+pub(super) fn write_row<W>(write: &mut W, row: &ValueRow, buf: &mut Vec<u8>) -> std::io::Result<()>
 where
     W: std::io::Write,
 {
@@ -203,11 +282,20 @@ where
     write.write_all(buf)?;
     Ok(())
 }
-fn read_row<R>(
+pub(super) fn read_row<R>(
     read: &mut R,
     atom_schemes: &[AtomScheme],
     buf: &mut Vec<u8>,
 ) -> std::io::Result<ValueRow>
+where
+    R: std::io::Read,
+{
+    read_row_bytes(read, buf)?;
+    let row = ValueRow::decode(atom_schemes, &mut std::io::Cursor::new(buf))
+        .ok_or(std::io::ErrorKind::InvalidInput)?;
+    Ok(row)
+}
+fn read_row_bytes<R>(read: &mut R, buf: &mut Vec<u8>) -> std::io::Result<()>
 where
     R: std::io::Read,
 {
@@ -216,16 +304,14 @@ where
     buf.clear();
     buf.extend(std::iter::repeat_n(0, len));
     read.read_exact(buf)?;
-    let row = ValueRow::decode(atom_schemes, &mut std::io::Cursor::new(buf))
-        .ok_or(std::io::ErrorKind::InvalidInput)?;
-    Ok(row)
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
         format::{AtomType, AtomValue},
-        serde::{FieldScheme, FieldType, ObjectScheme},
+        serde::{FieldScheme, FieldType, HdvSerialize, ObjectScheme},
     };
 
     use super::*;
@@ -239,18 +325,24 @@ mod tests {
         }
         impl HdvScheme for A {
             fn object_scheme() -> ObjectScheme {
-                ObjectScheme {
-                    fields: vec![
-                        FieldScheme {
-                            name: "a".to_string(),
-                            ty: FieldType::Atom(AtomType::I64),
-                        },
-                        FieldScheme {
-                            name: "b".to_string(),
-                            ty: FieldType::Atom(AtomType::F64),
-                        },
-                    ],
-                }
+                ObjectScheme::new(vec![
+                    FieldScheme {
+                        name: "a".to_string(),
+                        ty: FieldType::Atom(AtomType::I64),
+                        nullable: false,
+                        default: None,
+                        aliases: vec![],
+                        flatten: false,
+                    },
+                    FieldScheme {
+                        name: "b".to_string(),
+                        ty: FieldType::Atom(AtomType::F64),
+                        nullable: false,
+                        default: None,
+                        aliases: vec![],
+                        flatten: false,
+                    },
+                ])
             }
         }
         impl HdvSerialize for A {
@@ -327,4 +419,123 @@ mod tests {
         writer.flush().unwrap();
         assert_eq!(buf, buf_);
     }
+
+    #[test]
+    fn test_missing_columns() {
+        #[derive(Debug, PartialEq)]
+        struct C {
+            a: i64,
+            c: u64,
+            d: Option<u64>,
+        }
+        impl HdvScheme for C {
+            fn object_scheme() -> ObjectScheme {
+                ObjectScheme::new(vec![
+                    FieldScheme {
+                        name: "a".to_string(),
+                        ty: FieldType::Atom(AtomType::I64),
+                        nullable: false,
+                        default: None,
+                        aliases: vec![],
+                        flatten: false,
+                    },
+                    FieldScheme {
+                        name: "c".to_string(),
+                        ty: FieldType::Atom(AtomType::U64),
+                        nullable: false,
+                        default: Some(AtomValue::U64(7)),
+                        aliases: vec![],
+                        flatten: false,
+                    },
+                    FieldScheme {
+                        name: "d".to_string(),
+                        ty: FieldType::Atom(AtomType::U64),
+                        nullable: true,
+                        default: None,
+                        aliases: vec![],
+                        flatten: false,
+                    },
+                ])
+            }
+        }
+        impl HdvDeserialize for C {
+            fn deserialize(values: &mut &[Option<AtomValue>]) -> Option<Self> {
+                let a = values.first()?.as_ref();
+                let c = values.get(1)?.as_ref();
+                let d = values.get(2)?.as_ref();
+                *values = &values[3..];
+                Some(Self {
+                    a: a?.i64().unwrap(),
+                    c: c?.u64().unwrap(),
+                    d: d.map(|x| x.u64().unwrap()),
+                })
+            }
+        }
+
+        let header = vec![
+            AtomScheme {
+                name: "a".to_string(),
+                r#type: AtomType::I64,
+            },
+            AtomScheme {
+                name: "b".to_string(),
+                r#type: AtomType::F64,
+            },
+        ];
+        let mut buf = vec![];
+        let mut writer = HdvBinRawWriter::new(&mut buf, header);
+        writer
+            .write(&ValueRow::new(vec![
+                Some(AtomValue::I64(1)),
+                Some(AtomValue::F64(2.0)),
+            ]))
+            .unwrap();
+        writer.flush().unwrap();
+
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        let c: C = reader.read().unwrap();
+        assert_eq!(
+            c,
+            C {
+                a: 1,
+                c: 7,
+                d: None
+            }
+        );
+
+        struct E;
+        impl HdvScheme for E {
+            fn object_scheme() -> ObjectScheme {
+                ObjectScheme::new(vec![FieldScheme {
+                    name: "b".to_string(),
+                    ty: FieldType::Atom(AtomType::U64),
+                    nullable: false,
+                    default: None,
+                    aliases: vec![],
+                    flatten: false,
+                }])
+            }
+        }
+        impl HdvDeserialize for E {
+            fn deserialize(_values: &mut &[Option<AtomValue>]) -> Option<Self> {
+                Some(Self)
+            }
+        }
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        let e = reader.read().map(|_: E| ()).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(
+            e.to_string(),
+            "column `b` is of type `F64` which cannot be read as `U64`"
+        );
+    }
+
+    #[test]
+    fn test_recycle_buf() {
+        let buf = vec![Some(AtomValueRef::U64(1)), None];
+        let (ptr, capacity) = (buf.as_ptr() as usize, buf.capacity());
+        let buf = recycle_buf(buf);
+        assert!(buf.is_empty());
+        assert_eq!((buf.as_ptr() as usize, buf.capacity()), (ptr, capacity));
+    }
 }
@@ -0,0 +1,291 @@
+use crate::format::{AtomType, AtomValue, TimeUnit};
+
+use super::{text::write_atom, HdvReaderOptions};
+
+/// Conversion of a column whose stored type differs from the required one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Coercion {
+    /// Lossless as long as the value is in range
+    U64ToI64,
+    /// Lossless
+    F32ToF64,
+    /// Integers of another width
+    ///
+    /// Lossless as long as the value is in the inclusive range.
+    ResizeInt { min: i128, max: i128 },
+    /// Lossless
+    U64ToU128,
+    /// Lossless
+    IntToI128,
+    /// Opt-in; large integers lose precision
+    IntToF64,
+    /// Opt-in; values other than strings and bytes are in the text format
+    ///
+    /// Bytes are read as UTF-8.
+    ToString { from: AtomType },
+    /// Timestamps of the same time zone and durations in another unit
+    ///
+    /// Lossless as long as the value is in range and no precision is lost.
+    Rescale { from: TimeUnit, to: TimeUnit },
+    /// Decimals of another precision or scale
+    ///
+    /// Lossless as long as the value has at most `precision` digits and no precision is lost.
+    ScaleDecimal { from: u8, to: u8, precision: u8 },
+}
+impl Coercion {
+    /// Return [`None`] if columns of type `from` cannot be read as `to`.
+    pub fn new(from: &AtomType, to: &AtomType, options: &HdvReaderOptions) -> Option<Self> {
+        Some(match (from, to) {
+            (AtomType::U64, AtomType::I64) => Self::U64ToI64,
+            (AtomType::F32, AtomType::F64) => Self::F32ToF64,
+            (from, to) if from.int_range().is_some() && to.int_range().is_some() => {
+                let (from_min, _) = from.int_range().unwrap();
+                let (min, max) = to.int_range().unwrap();
+                // Signed integers are not read as unsigned ones
+                if from_min < 0 && min == 0 {
+                    return None;
+                }
+                Self::ResizeInt { min, max }
+            }
+            (from, AtomType::U128) if from.int_range().is_some_and(|(min, _)| min == 0) => {
+                Self::U64ToU128
+            }
+            (from, AtomType::I128) if from.int_range().is_some() => Self::IntToI128,
+            (from, AtomType::F64) if options.coerce_int_to_float && from.int_range().is_some() => {
+                Self::IntToF64
+            }
+            (from, AtomType::String) if options.coerce_to_string => {
+                Self::ToString { from: from.clone() }
+            }
+            (
+                AtomType::Timestamp { unit: from, tz: a },
+                AtomType::Timestamp { unit: to, tz: b },
+            ) if a == b => Self::Rescale {
+                from: *from,
+                to: *to,
+            },
+            (AtomType::Duration { unit: from }, AtomType::Duration { unit: to }) => Self::Rescale {
+                from: *from,
+                to: *to,
+            },
+            (AtomType::Decimal { scale: from, .. }, &AtomType::Decimal { precision, scale }) => {
+                Self::ScaleDecimal {
+                    from: *from,
+                    to: scale,
+                    precision,
+                }
+            }
+            _ => return None,
+        })
+    }
+
+    /// Return [`None`] if the value is not representable in the required type.
+    pub fn apply(&self, value: &AtomValue) -> Option<AtomValue> {
+        Some(match (self, value) {
+            (Self::U64ToI64, AtomValue::U64(x)) => AtomValue::I64((*x).try_into().ok()?),
+            (Self::F32ToF64, AtomValue::F32(x)) => AtomValue::F64((*x).into()),
+            (&Self::ResizeInt { min, max }, x) => {
+                let x = match x {
+                    AtomValue::U64(x) => i128::from(*x),
+                    AtomValue::I64(x) => i128::from(*x),
+                    _ => return None,
+                };
+                if !(min..=max).contains(&x) {
+                    return None;
+                }
+                if min < 0 {
+                    AtomValue::I64(x as i64)
+                } else {
+                    AtomValue::U64(x as u64)
+                }
+            }
+            (Self::U64ToU128, AtomValue::U64(x)) => AtomValue::U128((*x).into()),
+            (Self::IntToI128, AtomValue::U64(x)) => AtomValue::I128((*x).into()),
+            (Self::IntToI128, AtomValue::I64(x)) => AtomValue::I128((*x).into()),
+            (Self::IntToF64, AtomValue::U64(x)) => AtomValue::F64(*x as _),
+            (Self::IntToF64, AtomValue::I64(x)) => AtomValue::F64(*x as _),
+            (Self::ToString { from }, x) => {
+                let string = match x {
+                    AtomValue::String(x) => x.clone(),
+                    AtomValue::Bytes(x) => std::str::from_utf8(x).ok()?.into(),
+                    x => {
+                        let mut text = vec![];
+                        write_atom(&mut text, x, from).ok()?;
+                        String::from_utf8(text).ok()?.into()
+                    }
+                };
+                AtomValue::String(string)
+            }
+            (&Self::Rescale { from, to }, AtomValue::Timestamp(x)) => {
+                AtomValue::Timestamp(from.convert(*x, to)?)
+            }
+            (&Self::Rescale { from, to }, AtomValue::Duration(x)) => {
+                AtomValue::Duration(from.convert(*x, to)?)
+            }
+            (
+                &Self::ScaleDecimal {
+                    from,
+                    to,
+                    precision,
+                },
+                AtomValue::Decimal(x),
+            ) => {
+                let x = if from <= to {
+                    x.checked_mul(10_i128.checked_pow((to - from).into())?)?
+                } else {
+                    let factor = 10_i128.checked_pow((from - to).into())?;
+                    if x % factor != 0 {
+                        return None;
+                    }
+                    x / factor
+                };
+                let value = AtomValue::Decimal(x);
+                let scale = to;
+                if !(AtomType::Decimal { precision, scale }).is_type_of(&value) {
+                    return None;
+                }
+                value
+            }
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format::ItemType;
+
+    use super::*;
+
+    #[test]
+    fn test_coercion() {
+        let options = HdvReaderOptions::default();
+        assert_eq!(
+            Coercion::new(&AtomType::U64, &AtomType::I64, &options),
+            Some(Coercion::U64ToI64)
+        );
+        assert_eq!(
+            Coercion::new(&AtomType::I64, &AtomType::U64, &options),
+            None
+        );
+        assert_eq!(
+            Coercion::new(&AtomType::U64, &AtomType::F64, &options),
+            None
+        );
+        assert_eq!(
+            Coercion::new(&AtomType::F64, &AtomType::String, &options),
+            None
+        );
+
+        let options = HdvReaderOptions {
+            coerce_int_to_float: true,
+            coerce_to_string: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            Coercion::new(&AtomType::I64, &AtomType::F64, &options),
+            Some(Coercion::IntToF64)
+        );
+        assert_eq!(
+            Coercion::new(&AtomType::F64, &AtomType::String, &options),
+            Some(Coercion::ToString {
+                from: AtomType::F64
+            })
+        );
+        assert_eq!(
+            Coercion::new(&AtomType::String, &AtomType::U64, &options),
+            None
+        );
+
+        assert_eq!(
+            Coercion::U64ToI64.apply(&AtomValue::U64(1)),
+            Some(AtomValue::I64(1))
+        );
+        assert_eq!(Coercion::U64ToI64.apply(&AtomValue::U64(u64::MAX)), None);
+        let resize = Coercion::new(&AtomType::U64, &AtomType::U16, &options).unwrap();
+        assert_eq!(
+            resize.apply(&AtomValue::U64(65535)),
+            Some(AtomValue::U64(65535))
+        );
+        assert_eq!(resize.apply(&AtomValue::U64(70000)), None);
+        let resize = Coercion::new(&AtomType::U8, &AtomType::I16, &options).unwrap();
+        assert_eq!(resize.apply(&AtomValue::U64(1)), Some(AtomValue::I64(1)));
+        assert_eq!(Coercion::new(&AtomType::I8, &AtomType::U64, &options), None);
+        let scale_decimal = Coercion::new(
+            &AtomType::Decimal {
+                precision: 4,
+                scale: 2,
+            },
+            &AtomType::Decimal {
+                precision: 5,
+                scale: 3,
+            },
+            &options,
+        )
+        .unwrap();
+        assert_eq!(
+            scale_decimal.apply(&AtomValue::Decimal(-9999)),
+            Some(AtomValue::Decimal(-99990))
+        );
+        let scale_decimal = Coercion::ScaleDecimal {
+            from: 2,
+            to: 1,
+            precision: 38,
+        };
+        assert_eq!(scale_decimal.apply(&AtomValue::Decimal(125)), None);
+        assert_eq!(
+            Coercion::new(&AtomType::I64, &AtomType::I128, &options),
+            Some(Coercion::IntToI128)
+        );
+        assert_eq!(
+            Coercion::IntToI128.apply(&AtomValue::I64(-1)),
+            Some(AtomValue::I128(-1))
+        );
+        assert_eq!(
+            Coercion::F32ToF64.apply(&AtomValue::F32(0.5)),
+            Some(AtomValue::F64(0.5))
+        );
+        let to_string = |from: AtomType, value: AtomValue| {
+            let coercion = Coercion::new(&from, &AtomType::String, &options).unwrap();
+            coercion.apply(&value)
+        };
+        assert_eq!(
+            to_string(AtomType::Bool, AtomValue::Bool(true)),
+            Some(AtomValue::String("true".into()))
+        );
+        assert_eq!(
+            to_string(AtomType::Bytes, AtomValue::Bytes([0xff].as_ref().into())),
+            None
+        );
+        let timestamp = AtomType::Timestamp {
+            unit: TimeUnit::Microseconds,
+            tz: Some("UTC".into()),
+        };
+        assert_eq!(
+            to_string(timestamp, AtomValue::Timestamp(1_500_000)),
+            Some(AtomValue::String("1970-01-01T00:00:01.500000Z".into()))
+        );
+        let decimal = AtomType::Decimal {
+            precision: 5,
+            scale: 2,
+        };
+        assert_eq!(
+            to_string(decimal, AtomValue::Decimal(-12345)),
+            Some(AtomValue::String("-123.45".into()))
+        );
+        assert_eq!(
+            to_string(
+                AtomType::List(ItemType::U64),
+                AtomValue::List(
+                    ItemType::U64,
+                    vec![AtomValue::U64(1), AtomValue::U64(2)].into()
+                )
+            ),
+            Some(AtomValue::String("[1;2]".into()))
+        );
+        assert_eq!(
+            to_string(AtomType::I128, AtomValue::I128(-1)),
+            Some(AtomValue::String("-1".into()))
+        );
+    }
+}
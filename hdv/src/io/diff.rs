@@ -0,0 +1,253 @@
+use crate::{
+    format::{AtomScheme, AtomType},
+    serde::{FieldScheme, FieldType, ObjectScheme},
+};
+
+use super::{field_names, HdvReaderOptions, HdvShiftedHeader};
+
+/// Difference between the columns of an old scheme and a new one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemeDiff {
+    pub changes: Vec<SchemeChange>,
+    /// Whether the new reader can read files written with the old scheme.
+    ///
+    /// The error explains the first column the new reader fails to locate.
+    pub backward: Result<(), String>,
+    /// Whether the old reader can read files written with the new scheme.
+    ///
+    /// The error explains the first column the old reader fails to locate.
+    pub forward: Result<(), String>,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemeChange {
+    /// Column only in the new scheme
+    Added(AtomScheme),
+    /// Column only in the old scheme
+    Removed(AtomScheme),
+    /// Column whose type differs between the schemes
+    ///
+    /// `name` is the name in the new scheme.
+    TypeChanged {
+        name: String,
+        old: AtomType,
+        new: AtomType,
+    },
+    /// Column of the old scheme read by the new scheme through an alias of a field or of [`HdvReaderOptions::aliases`]
+    Renamed { old: String, new: String },
+}
+impl SchemeDiff {
+    pub fn new(old: &ObjectScheme, new: &ObjectScheme, options: &HdvReaderOptions) -> Self {
+        let old_header = old.atom_schemes();
+        let new_header = new.atom_schemes();
+        Self {
+            changes: changes(&old_header, new, options),
+            backward: compatibility(old_header, new, options),
+            forward: compatibility(new_header, old, options),
+        }
+    }
+
+    /// Diff against a file header.
+    ///
+    /// The old reader is assumed to require every column of `old`.
+    pub fn from_header(old: &[AtomScheme], new: &ObjectScheme, options: &HdvReaderOptions) -> Self {
+        let old_object = ObjectScheme::new(
+            old.iter()
+                .map(|x| FieldScheme {
+                    name: x.name.clone(),
+                    ty: FieldType::Atom(x.r#type.clone()),
+                    nullable: false,
+                    default: None,
+                    aliases: vec![],
+                    flatten: false,
+                })
+                .collect(),
+        );
+        Self {
+            changes: changes(old, new, options),
+            backward: compatibility(old.to_vec(), new, options),
+            forward: compatibility(new.atom_schemes(), &old_object, options),
+        }
+    }
+
+    pub fn is_backward_compatible(&self) -> bool {
+        self.backward.is_ok()
+    }
+
+    pub fn is_forward_compatible(&self) -> bool {
+        self.forward.is_ok()
+    }
+}
+
+fn compatibility(
+    header: Vec<AtomScheme>,
+    reader: &ObjectScheme,
+    options: &HdvReaderOptions,
+) -> Result<(), String> {
+    HdvShiftedHeader::new(header, reader, options)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Old columns are looked up by their names after applying [`HdvReaderOptions::aliases`].
+fn changes(
+    old: &[AtomScheme],
+    new: &ObjectScheme,
+    options: &HdvReaderOptions,
+) -> Vec<SchemeChange> {
+    let column_name = |column: &AtomScheme| match options.aliases.get(&column.name) {
+        Some(x) => x.clone(),
+        None => column.name.clone(),
+    };
+    let mut leaves = vec![];
+    atom_leaves(new, &[], "", &mut leaves);
+
+    let mut changes = vec![];
+    let mut is_matched = vec![false; old.len()];
+    for (names, ty) in leaves {
+        let found = names.iter().enumerate().find_map(|(i, name)| {
+            let index = old.iter().position(|x| column_name(x) == *name)?;
+            Some((i, index))
+        });
+        let Some((i, index)) = found else {
+            changes.push(SchemeChange::Added(AtomScheme {
+                name: names[0].clone(),
+                r#type: ty,
+            }));
+            continue;
+        };
+        is_matched[index] = true;
+        let column = &old[index];
+        if i != 0 || column.name != names[0] {
+            changes.push(SchemeChange::Renamed {
+                old: column.name.clone(),
+                new: names[0].clone(),
+            });
+        }
+        if column.r#type != ty {
+            changes.push(SchemeChange::TypeChanged {
+                name: names[0].clone(),
+                old: column.r#type.clone(),
+                new: ty,
+            });
+        }
+    }
+    for (column, is_matched) in old.iter().zip(is_matched) {
+        if !is_matched {
+            changes.push(SchemeChange::Removed(column.clone()));
+        }
+    }
+    changes
+}
+
+/// Collect the candidate names and the type of every atom field
+fn atom_leaves(
+    object: &ObjectScheme,
+    prefixes: &[String],
+    separator: &str,
+    leaves: &mut Vec<(Vec<String>, AtomType)>,
+) {
+    for field in &object.fields {
+        let names = field_names(prefixes, separator, field);
+        if field.nullable && matches!(field.ty, FieldType::Object(_)) {
+            leaves.push((names.clone(), AtomType::Bool));
+        }
+        match &field.ty {
+            FieldType::Atom(ty) => leaves.push((names, ty.clone())),
+            FieldType::Object(nested) if field.flatten => {
+                atom_leaves(nested, prefixes, separator, leaves)
+            }
+            FieldType::Object(nested) => atom_leaves(nested, &names, &object.separator, leaves),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, ty: AtomType) -> FieldScheme {
+        FieldScheme {
+            name: name.to_string(),
+            ty: FieldType::Atom(ty),
+            nullable: false,
+            default: None,
+            aliases: vec![],
+            flatten: false,
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = ObjectScheme::new(vec![
+            field("a", AtomType::U64),
+            field("b", AtomType::String),
+            field("c", AtomType::F32),
+        ]);
+        let new = ObjectScheme::new(vec![
+            FieldScheme {
+                aliases: vec!["a".to_string()],
+                ..field("x", AtomType::U64)
+            },
+            field("c", AtomType::F64),
+            FieldScheme {
+                nullable: true,
+                ..field("d", AtomType::Bool)
+            },
+        ]);
+        let options = HdvReaderOptions::default();
+        let diff = SchemeDiff::new(&old, &new, &options);
+        assert_eq!(
+            diff.changes,
+            [
+                SchemeChange::Renamed {
+                    old: "a".to_string(),
+                    new: "x".to_string(),
+                },
+                SchemeChange::TypeChanged {
+                    name: "c".to_string(),
+                    old: AtomType::F32,
+                    new: AtomType::F64,
+                },
+                SchemeChange::Added(AtomScheme {
+                    name: "d".to_string(),
+                    r#type: AtomType::Bool,
+                }),
+                SchemeChange::Removed(AtomScheme {
+                    name: "b".to_string(),
+                    r#type: AtomType::String,
+                }),
+            ]
+        );
+        assert!(diff.is_backward_compatible());
+        assert_eq!(diff.forward, Err("column `a` is missing".to_string()));
+
+        let diff = SchemeDiff::from_header(&new.atom_schemes(), &old, &options);
+        assert!(!diff.is_backward_compatible());
+        assert_eq!(diff.forward, Err("column `x` is missing".to_string()));
+
+        // Renamed through the reader options
+        let options = HdvReaderOptions {
+            aliases: [("b".to_string(), "y".to_string())].into(),
+            ..Default::default()
+        };
+        let new = ObjectScheme::new(vec![
+            field("a", AtomType::U64),
+            field("y", AtomType::String),
+        ]);
+        let diff = SchemeDiff::new(&old, &new, &options);
+        assert_eq!(
+            diff.changes,
+            [
+                SchemeChange::Renamed {
+                    old: "b".to_string(),
+                    new: "y".to_string(),
+                },
+                SchemeChange::Removed(AtomScheme {
+                    name: "c".to_string(),
+                    r#type: AtomType::F32,
+                }),
+            ]
+        );
+        assert!(diff.is_backward_compatible());
+    }
+}
@@ -1,27 +1,60 @@
+use std::collections::HashMap;
+
+use coercion::Coercion;
+
 use crate::{
-    format::{AtomScheme, AtomValue, ValueRow},
-    serde::ObjectScheme,
+    format::{AtomScheme, AtomType, AtomValue, AtomValueRef, ValueRow},
+    serde::{FieldScheme, FieldType, ObjectScheme},
 };
 
 pub mod bin;
+mod coercion;
+pub mod diff;
+pub mod multi;
 #[cfg(feature = "polars")]
 pub mod polars;
 pub mod text;
 
+#[derive(Debug, Clone, Default)]
+pub struct HdvReaderOptions {
+    /// Read integer columns into float fields, losing precision of large integers
+    pub coerce_int_to_float: bool,
+    /// Read columns of any type into string fields in the text format, and bytes as UTF-8
+    ///
+    /// Borrowed reads reject such columns since the strings are not in the row buffer.
+    pub coerce_to_string: bool,
+    /// Map from column names in the file to the names the reader looks them up by
+    pub aliases: HashMap<String, String>,
+}
+
 #[derive(Debug)]
 struct HdvShiftedHeader {
     header: Vec<AtomScheme>,
-    column_shifting: Vec<usize>,
+    column_shifting: Vec<ColumnShift>,
+}
+#[derive(Debug)]
+enum ColumnShift {
+    /// Index of the column in the file header
+    Column {
+        index: usize,
+        coercion: Option<Coercion>,
+    },
+    /// Value of a column missing from the file
+    Fallback(Option<AtomValue>),
 }
 impl HdvShiftedHeader {
-    pub fn new(header: Vec<AtomScheme>, object_scheme: &ObjectScheme) -> Option<Self> {
-        let required = object_scheme.atom_schemes();
+    pub fn new(
+        header: Vec<AtomScheme>,
+        object_scheme: &ObjectScheme,
+        options: &HdvReaderOptions,
+    ) -> std::io::Result<Self> {
         let mut column_shifting = vec![];
-        for required in &required {
-            let i = header.iter().position(|x| x == required)?;
-            column_shifting.push(i);
-        }
-        Some(Self {
+        let cx = ShiftContext {
+            header: &header,
+            options,
+        };
+        shift_object(&cx, object_scheme, &[], "", false, &mut column_shifting)?;
+        Ok(Self {
             header,
             column_shifting,
         })
@@ -31,20 +64,237 @@ impl HdvShiftedHeader {
         &self.header
     }
 
-    pub fn shift(&self, source: &[Option<AtomValue>], values: &mut Vec<Option<AtomValue>>) {
-        for i in self.column_shifting.iter().copied() {
-            let value = source[i].clone();
+    pub fn shift(
+        &self,
+        source: &[Option<AtomValue>],
+        values: &mut Vec<Option<AtomValue>>,
+    ) -> std::io::Result<()> {
+        for shift in &self.column_shifting {
+            let value = match shift {
+                ColumnShift::Column { index, coercion } => match (&source[*index], coercion) {
+                    (Some(value), Some(coercion)) => {
+                        let value = coercion.apply(value).ok_or_else(|| {
+                            let name = &self.header[*index].name;
+                            let message =
+                                format!("column `{name}`: value `{value:?}` cannot be coerced");
+                            std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+                        })?;
+                        Some(value)
+                    }
+                    (value, _) => value.clone(),
+                },
+                ColumnShift::Fallback(x) => x.clone(),
+            };
             values.push(value);
         }
+        Ok(())
+    }
+
+    /// Reject coercions into strings, whose values cannot borrow from a row buffer
+    pub fn check_borrowed(&self) -> std::io::Result<()> {
+        for shift in &self.column_shifting {
+            if let ColumnShift::Column {
+                index,
+                coercion: Some(Coercion::ToString { .. }),
+            } = shift
+            {
+                let name = &self.header[*index].name;
+                let message = format!("column `{name}` cannot be coerced into a borrowed string");
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    message,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// [`Self::shift`] for values borrowing from a row buffer
+    ///
+    /// Coercions into strings are rejected by [`Self::check_borrowed`].
+    pub fn shift_borrowed<'a>(
+        &'a self,
+        source: &[Option<AtomValueRef<'a>>],
+        values: &mut Vec<Option<AtomValueRef<'a>>>,
+    ) -> std::io::Result<()> {
+        for shift in &self.column_shifting {
+            let value = match shift {
+                ColumnShift::Column { index, coercion } => match (&source[*index], coercion) {
+                    (Some(value), Some(coercion)) => {
+                        let coerced = coercion
+                            .apply(&value.to_owned())
+                            .and_then(AtomValueRef::from_scalar);
+                        let value = coerced.ok_or_else(|| {
+                            let name = &self.header[*index].name;
+                            let message = format!(
+                                "column `{name}`: value `{value:?}` cannot be coerced into a borrowed value"
+                            );
+                            std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+                        })?;
+                        Some(value)
+                    }
+                    (value, _) => *value,
+                },
+                ColumnShift::Fallback(x) => x.as_ref().map(AtomValueRef::from),
+            };
+            values.push(value);
+        }
+        Ok(())
+    }
+}
+
+struct ShiftContext<'a> {
+    header: &'a [AtomScheme],
+    options: &'a HdvReaderOptions,
+}
+impl ShiftContext<'_> {
+    /// Name of a column after applying [`HdvReaderOptions::aliases`]
+    fn column_name<'a>(&'a self, column: &'a AtomScheme) -> &'a str {
+        match self.options.aliases.get(&column.name) {
+            Some(x) => x,
+            None => &column.name,
+        }
+    }
+
+    /// Prefer earlier names over later ones and then the column of the exact type over the coercible ones.
+    fn find_column(&self, names: &[String], ty: &AtomType) -> Option<ColumnShift> {
+        names.iter().find_map(|name| {
+            let mut columns = self
+                .header
+                .iter()
+                .enumerate()
+                .filter(|(_, x)| self.column_name(x) == name);
+            if let Some((index, _)) = columns.clone().find(|(_, x)| x.r#type == *ty) {
+                return Some(ColumnShift::Column {
+                    index,
+                    coercion: None,
+                });
+            }
+            columns.find_map(|(index, x)| {
+                let coercion = Coercion::new(&x.r#type, ty, self.options)?;
+                Some(ColumnShift::Column {
+                    index,
+                    coercion: Some(coercion),
+                })
+            })
+        })
+    }
+
+    fn missing_column(&self, names: &[String], ty: &AtomType) -> std::io::Error {
+        let name = &names[0];
+        let column = self
+            .header
+            .iter()
+            .find(|x| names.iter().any(|name| self.column_name(x) == name));
+        let message = match column {
+            Some(x) => format!(
+                "column `{}` is of type `{:?}` which cannot be read as `{ty:?}`",
+                x.name, x.r#type
+            ),
+            None => format!("column `{name}` is missing"),
+        };
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
     }
 }
 
-fn assert_atom_types(header: &[AtomScheme], row: &ValueRow) {
+/// Locate the columns of `object` in `header`.
+///
+/// A field is looked up by its name first and then by its aliases.
+/// `separator` joins `prefixes` with the names of the fields.
+/// A missing column falls back to the default of its field.
+/// Without a default, it falls back to null if the field or any of its parents is nullable.
+/// A nullable object with all of its columns missing is null as a whole unless the field has a default.
+/// A missing validity column of a nullable object falls back to its default
+/// or else to `true`, leaving the nullness to its nested columns.
+fn shift_object(
+    cx: &ShiftContext<'_>,
+    object: &ObjectScheme,
+    prefixes: &[String],
+    separator: &str,
+    is_parent_nullable: bool,
+    column_shifting: &mut Vec<ColumnShift>,
+) -> std::io::Result<()> {
+    for field in &object.fields {
+        let names = field_names(prefixes, separator, field);
+        let is_nullable = is_parent_nullable || field.nullable;
+        match &field.ty {
+            FieldType::Atom(ty) => {
+                let shift = match (cx.find_column(&names, ty), &field.default) {
+                    (Some(shift), _) => shift,
+                    (None, Some(default)) => ColumnShift::Fallback(Some(default.clone())),
+                    (None, None) if is_nullable => ColumnShift::Fallback(None),
+                    (None, None) => return Err(cx.missing_column(&names, ty)),
+                };
+                column_shifting.push(shift);
+            }
+            FieldType::Object(nested) => {
+                let start = column_shifting.len();
+                if field.nullable {
+                    let default = field.default.clone().unwrap_or(AtomValue::Bool(true));
+                    let shift = cx
+                        .find_column(&names, &AtomType::Bool)
+                        .unwrap_or(ColumnShift::Fallback(Some(default)));
+                    column_shifting.push(shift);
+                }
+                let (prefixes, separator) = if field.flatten {
+                    (prefixes, separator)
+                } else {
+                    (names.as_slice(), object.separator.as_str())
+                };
+                shift_object(
+                    cx,
+                    nested,
+                    prefixes,
+                    separator,
+                    is_nullable,
+                    column_shifting,
+                )?;
+                let shifting = &mut column_shifting[start..];
+                let is_absent = shifting
+                    .iter()
+                    .all(|x| matches!(x, ColumnShift::Fallback(_)));
+                if field.nullable && field.default.is_none() && is_absent {
+                    shifting.fill_with(|| ColumnShift::Fallback(None));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+/// Full names of a field with the canonical one first
+fn field_names(prefixes: &[String], separator: &str, field: &FieldScheme) -> Vec<String> {
+    let names = std::iter::once(&field.name).chain(&field.aliases);
+    if prefixes.is_empty() {
+        return names.cloned().collect();
+    }
+    prefixes
+        .iter()
+        .flat_map(|prefix| {
+            names
+                .clone()
+                .map(move |name| format!("{prefix}{separator}{name}"))
+        })
+        .collect()
+}
+
+/// Return an error of [`std::io::ErrorKind::InvalidInput`] if a value does not fit its column.
+///
+/// # Panic
+///
+/// If the row is not as wide as the header.
+fn check_atom_types(header: &[AtomScheme], row: &ValueRow) -> std::io::Result<()> {
     assert_eq!(header.len(), row.atoms().len());
     for (a, b) in header.iter().zip(row.atoms().iter()) {
         let Some(b) = b else {
             continue;
         };
-        assert_eq!(a.r#type, b.into());
+        if !a.r#type.is_type_of(b) {
+            let message = format!("column `{}`: `{b:?}` is not of `{:?}`", a.name, a.r#type);
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                message,
+            ));
+        }
     }
+    Ok(())
 }
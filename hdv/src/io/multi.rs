@@ -0,0 +1,310 @@
+use std::collections::{hash_map::Entry, HashMap};
+
+use integer_encoding::{VarIntReader, VarIntWriter};
+
+use crate::{
+    format::{AtomScheme, AtomValue, ValueRow},
+    serde::{HdvDeserialize, HdvScheme, HdvSerialize, ObjectScheme},
+};
+
+use super::{
+    bin::{read_header, read_row, write_header, write_row},
+    check_atom_types, HdvReaderOptions, HdvShiftedHeader,
+};
+
+/// Binary stream holding rows of several schemas.
+///
+/// Every record starts with the tag of its schema.
+/// The first record of a tag also carries the header of that tag.
+#[derive(Debug)]
+pub struct HdvBinMultiRawWriter<W> {
+    headers: HashMap<u64, TaggedHeader>,
+    write: W,
+    buf: Vec<u8>,
+}
+#[derive(Debug)]
+struct TaggedHeader {
+    header: Vec<AtomScheme>,
+    has_written_header: bool,
+}
+impl<W> HdvBinMultiRawWriter<W> {
+    pub fn new(write: W) -> Self {
+        Self {
+            headers: HashMap::new(),
+            write,
+            buf: vec![],
+        }
+    }
+
+    /// # Panic
+    ///
+    /// If `tag` has already been registered.
+    pub fn register(&mut self, tag: u64, header: Vec<AtomScheme>) {
+        let prev = self.headers.insert(
+            tag,
+            TaggedHeader {
+                header,
+                has_written_header: false,
+            },
+        );
+        assert!(prev.is_none(), "tag `{tag}` has already been registered");
+    }
+}
+impl<W> HdvBinMultiRawWriter<W>
+where
+    W: std::io::Write,
+{
+    /// Return an error of [`std::io::ErrorKind::InvalidInput`] if `tag` is not registered
+    /// or a value is not of the type of its column.
+    ///
+    /// # Panic
+    ///
+    /// If `row` is not as wide as the header registered to `tag`.
+    pub fn write(&mut self, tag: u64, row: &ValueRow) -> std::io::Result<()> {
+        let tagged = self.headers.get_mut(&tag).ok_or_else(|| {
+            let message = format!("tag `{tag}` is not registered");
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
+        })?;
+        check_atom_types(&tagged.header, row)?;
+
+        self.write.write_varint(tag)?;
+        if !tagged.has_written_header {
+            tagged.has_written_header = true;
+
+            write_header(&mut self.write, &tagged.header)?;
+        }
+
+        write_row(&mut self.write, row, &mut self.buf)?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.write.flush()
+    }
+}
+
+#[derive(Debug)]
+pub struct HdvBinMultiWriter<W> {
+    raw: HdvBinMultiRawWriter<W>,
+}
+impl<W> HdvBinMultiWriter<W> {
+    pub fn new(write: W) -> Self {
+        Self {
+            raw: HdvBinMultiRawWriter::new(write),
+        }
+    }
+
+    /// # Panic
+    ///
+    /// If `tag` has already been registered.
+    pub fn register<O>(&mut self, tag: u64)
+    where
+        O: HdvScheme,
+    {
+        let header = O::object_scheme().atom_schemes();
+        self.raw.register(tag, header);
+    }
+}
+impl<W> HdvBinMultiWriter<W>
+where
+    W: std::io::Write,
+{
+    /// Return an error of [`std::io::ErrorKind::InvalidInput`] if `tag` is not registered
+    /// or a value of `object` is not of the type of its column,
+    /// which is the case for most types other than the one registered to `tag`.
+    ///
+    /// # Panic
+    ///
+    /// If `object` is not as wide as the header registered to `tag`.
+    pub fn write<O>(&mut self, tag: u64, object: &O) -> std::io::Result<()>
+    where
+        O: HdvSerialize,
+    {
+        let mut atoms = vec![];
+        object.serialize(&mut atoms);
+
+        let row = ValueRow::new(atoms);
+        self.raw.write(tag, &row)
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.raw.flush()
+    }
+}
+
+#[derive(Debug)]
+pub struct HdvBinMultiRawReader<R> {
+    headers: HashMap<u64, Vec<AtomScheme>>,
+    read: R,
+    buf: Vec<u8>,
+}
+impl<R> HdvBinMultiRawReader<R> {
+    pub fn new(read: R) -> Self {
+        Self {
+            headers: HashMap::new(),
+            read,
+            buf: vec![],
+        }
+    }
+
+    pub fn header(&self, tag: u64) -> Option<&Vec<AtomScheme>> {
+        self.headers.get(&tag)
+    }
+}
+impl<R> HdvBinMultiRawReader<R>
+where
+    R: std::io::Read,
+{
+    pub fn read(&mut self) -> std::io::Result<(u64, ValueRow)> {
+        let tag = self.read.read_varint()?;
+        let header = match self.headers.entry(tag) {
+            Entry::Occupied(x) => x.into_mut(),
+            Entry::Vacant(x) => x.insert(read_header(&mut self.read)?),
+        };
+
+        let row = read_row(&mut self.read, header, &mut self.buf)?;
+        Ok((tag, row))
+    }
+}
+
+/// Reads rows of several schemas and dispatches each row to the type registered to its tag.
+///
+/// Rows whose tag is not registered are skipped.
+pub struct HdvBinMultiReader<R, T> {
+    options: HdvReaderOptions,
+    raw: HdvBinMultiRawReader<R>,
+    dispatches: HashMap<u64, Dispatch<T>>,
+    atom_value_buf: Vec<Option<AtomValue>>,
+}
+struct Dispatch<T> {
+    object_scheme: ObjectScheme,
+    shift_header: Option<HdvShiftedHeader>,
+    deserialize: Box<DeserializeFn<T>>,
+}
+type DeserializeFn<T> = dyn Fn(&mut &[Option<AtomValue>]) -> Option<T>;
+impl<R, T> HdvBinMultiReader<R, T> {
+    pub fn new(read: R) -> Self {
+        Self::with_options(read, HdvReaderOptions::default())
+    }
+
+    pub fn with_options(read: R, options: HdvReaderOptions) -> Self {
+        Self {
+            options,
+            raw: HdvBinMultiRawReader::new(read),
+            dispatches: HashMap::new(),
+            atom_value_buf: vec![],
+        }
+    }
+
+    /// Rows tagged with `tag` are deserialized as `O` and then mapped into `T`.
+    ///
+    /// # Panic
+    ///
+    /// If `tag` has already been registered.
+    pub fn register<O, F>(&mut self, tag: u64, map: F)
+    where
+        O: HdvDeserialize + HdvScheme + 'static,
+        F: Fn(O) -> T + 'static,
+    {
+        let dispatch = Dispatch {
+            object_scheme: O::object_scheme(),
+            shift_header: None,
+            deserialize: Box::new(move |values| O::deserialize(values).map(&map)),
+        };
+        let prev = self.dispatches.insert(tag, dispatch);
+        assert!(prev.is_none(), "tag `{tag}` has already been registered");
+    }
+}
+impl<R, T> HdvBinMultiReader<R, T>
+where
+    R: std::io::Read,
+{
+    pub fn read(&mut self) -> std::io::Result<T> {
+        loop {
+            let (tag, row) = self.raw.read()?;
+            let Some(dispatch) = self.dispatches.get_mut(&tag) else {
+                continue;
+            };
+            let shift_header = match &mut dispatch.shift_header {
+                Some(x) => x,
+                None => {
+                    let header = self.raw.header(tag).unwrap().clone();
+                    let shift_header =
+                        HdvShiftedHeader::new(header, &dispatch.object_scheme, &self.options)?;
+                    dispatch.shift_header.insert(shift_header)
+                }
+            };
+
+            self.atom_value_buf.clear();
+            shift_header.shift(row.atoms(), &mut self.atom_value_buf)?;
+
+            let object = (dispatch.deserialize)(&mut self.atom_value_buf.as_slice())
+                .ok_or(std::io::ErrorKind::InvalidData)?;
+            return Ok(object);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format::AtomType;
+
+    use super::*;
+
+    #[test]
+    fn test_io() {
+        let header_a = vec![AtomScheme {
+            name: "a".to_string(),
+            r#type: AtomType::U64,
+        }];
+        let header_b = vec![
+            AtomScheme {
+                name: "b".to_string(),
+                r#type: AtomType::String,
+            },
+            AtomScheme {
+                name: "c".to_string(),
+                r#type: AtomType::Bool,
+            },
+        ];
+        let rows = [
+            (0, ValueRow::new(vec![Some(AtomValue::U64(1))])),
+            (
+                1,
+                ValueRow::new(vec![Some(AtomValue::String("x".into())), None]),
+            ),
+            (0, ValueRow::new(vec![None])),
+            (
+                1,
+                ValueRow::new(vec![
+                    Some(AtomValue::String("y".into())),
+                    Some(AtomValue::Bool(true)),
+                ]),
+            ),
+        ];
+
+        let mut buf = vec![];
+        let mut writer = HdvBinMultiRawWriter::new(&mut buf);
+        writer.register(0, header_a.clone());
+        writer.register(1, header_b.clone());
+        for (tag, row) in &rows {
+            writer.write(*tag, row).unwrap();
+        }
+        writer.flush().unwrap();
+
+        let mut reader = HdvBinMultiRawReader::new(std::io::Cursor::new(&buf));
+        for (tag, row) in &rows {
+            let (tag_, row_) = reader.read().unwrap();
+            assert_eq!(*tag, tag_);
+            assert_eq!(*row, row_);
+        }
+        let e = reader.read().unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert_eq!(reader.header(0), Some(&header_a));
+        assert_eq!(reader.header(1), Some(&header_b));
+
+        let mut writer = HdvBinMultiRawWriter::new(vec![]);
+        let e = writer.write(2, &rows[0].1).unwrap_err();
+        assert_eq!(e.to_string(), "tag `2` is not registered");
+    }
+}
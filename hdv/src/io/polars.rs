@@ -1,7 +1,7 @@
-use polars::prelude::Column;
+use polars::prelude::{Column, IntoColumn, IntoSeries, NewChunkedArray};
 
 use crate::{
-    format::{AtomScheme, AtomType, AtomValue, ValueRow},
+    format::{AtomScheme, AtomType, AtomValue, ItemType, TimeUnit, ValueRow},
     io::bin::HdvBinRawWriter,
 };
 
@@ -61,7 +61,7 @@ where
         None => return Ok(polars::frame::DataFrame::empty()),
     };
 
-    Ok(hdv_polars_read(rows.iter(), header))
+    hdv_polars_read(rows.iter(), header).ok_or(std::io::ErrorKind::InvalidData.into())
 }
 pub fn hdv_text_polars_read<R>(read: R) -> std::io::Result<polars::frame::DataFrame>
 where
@@ -87,7 +87,7 @@ where
         None => return Ok(polars::frame::DataFrame::empty()),
     };
 
-    Ok(hdv_polars_read(rows.iter(), header))
+    hdv_polars_read(rows.iter(), header).ok_or(std::io::ErrorKind::InvalidData.into())
 }
 
 fn hdv_polars_write(df: &polars::frame::DataFrame) -> Option<(Vec<ValueRow>, Vec<AtomScheme>)> {
@@ -95,20 +95,16 @@ fn hdv_polars_write(df: &polars::frame::DataFrame) -> Option<(Vec<ValueRow>, Vec
     let mut header = vec![];
     for series in series_array {
         let atom_type = match series.dtype() {
-            polars::datatypes::DataType::Boolean => AtomType::Bool,
-            polars::datatypes::DataType::UInt8
-            | polars::datatypes::DataType::UInt16
-            | polars::datatypes::DataType::UInt32
-            | polars::datatypes::DataType::UInt64 => AtomType::U64,
-            polars::datatypes::DataType::Int8
-            | polars::datatypes::DataType::Int16
-            | polars::datatypes::DataType::Int32
-            | polars::datatypes::DataType::Int64 => AtomType::I64,
-            polars::datatypes::DataType::Float32 => AtomType::F32,
-            polars::datatypes::DataType::Float64 => AtomType::F64,
-            polars::datatypes::DataType::String => AtomType::String,
-            polars::datatypes::DataType::Binary => AtomType::Bytes,
-            _ => return None,
+            polars::datatypes::DataType::List(inner) => {
+                // Items of narrow integers are widened
+                let item_type = match scalar_atom_type(inner)? {
+                    AtomType::U8 | AtomType::U16 | AtomType::U32 => AtomType::U64,
+                    AtomType::I8 | AtomType::I16 | AtomType::I32 => AtomType::I64,
+                    x => x,
+                };
+                AtomType::List(ItemType::from_atom_type(&item_type)?)
+            }
+            dtype => scalar_atom_type(dtype)?,
         };
         header.push(AtomScheme {
             name: series.name().to_string(),
@@ -117,61 +113,7 @@ fn hdv_polars_write(df: &polars::frame::DataFrame) -> Option<(Vec<ValueRow>, Vec
     }
     let mut columns = vec![];
     for (scheme, series) in header.iter().zip(series_array.iter()) {
-        let column: Vec<Option<AtomValue>> = match scheme.r#type {
-            AtomType::String => series
-                .str()
-                .unwrap()
-                .iter()
-                .map(|x| x.map(|x| x.into()).map(AtomValue::String))
-                .collect(),
-            AtomType::Bytes => series
-                .binary()
-                .unwrap()
-                .iter()
-                .map(|x| x.map(|x| x.into()).map(AtomValue::Bytes))
-                .collect(),
-            AtomType::U64 => series
-                .cast(&polars::datatypes::DataType::UInt64)
-                .unwrap()
-                .u64()
-                .unwrap()
-                .iter()
-                .map(|x| x.map(AtomValue::U64))
-                .collect(),
-            AtomType::I64 => series
-                .cast(&polars::datatypes::DataType::Int64)
-                .unwrap()
-                .i64()
-                .unwrap()
-                .iter()
-                .map(|x| x.map(AtomValue::I64))
-                .collect(),
-            AtomType::F32 => series
-                .cast(&polars::datatypes::DataType::Float32)
-                .unwrap()
-                .f32()
-                .unwrap()
-                .iter()
-                .map(|x| x.map(AtomValue::F32))
-                .collect(),
-            AtomType::F64 => series
-                .cast(&polars::datatypes::DataType::Float64)
-                .unwrap()
-                .f64()
-                .unwrap()
-                .iter()
-                .map(|x| x.map(AtomValue::F64))
-                .collect(),
-            AtomType::Bool => series
-                .cast(&polars::datatypes::DataType::Boolean)
-                .unwrap()
-                .bool()
-                .unwrap()
-                .iter()
-                .map(|x| x.map(AtomValue::Bool))
-                .collect(),
-        };
-        columns.push(column);
+        columns.push(atom_values(series, &scheme.r#type)?);
     }
     let mut rows = vec![];
     let len = columns.first()?.len();
@@ -185,10 +127,143 @@ fn hdv_polars_write(df: &polars::frame::DataFrame) -> Option<(Vec<ValueRow>, Vec
     }
     Some((rows, header))
 }
+fn scalar_atom_type(dtype: &polars::datatypes::DataType) -> Option<AtomType> {
+    Some(match dtype {
+        polars::datatypes::DataType::Boolean => AtomType::Bool,
+        polars::datatypes::DataType::UInt8 => AtomType::U8,
+        polars::datatypes::DataType::UInt16 => AtomType::U16,
+        polars::datatypes::DataType::UInt32 => AtomType::U32,
+        polars::datatypes::DataType::UInt64 => AtomType::U64,
+        polars::datatypes::DataType::Int8 => AtomType::I8,
+        polars::datatypes::DataType::Int16 => AtomType::I16,
+        polars::datatypes::DataType::Int32 => AtomType::I32,
+        polars::datatypes::DataType::Int64 => AtomType::I64,
+        polars::datatypes::DataType::Float32 => AtomType::F32,
+        polars::datatypes::DataType::Float64 => AtomType::F64,
+        polars::datatypes::DataType::String => AtomType::String,
+        polars::datatypes::DataType::Binary => AtomType::Bytes,
+        polars::datatypes::DataType::Datetime(unit, tz) => AtomType::Timestamp {
+            unit: hdv_time_unit(*unit),
+            tz: tz.as_ref().map(|x| x.as_str().into()),
+        },
+        polars::datatypes::DataType::Date => AtomType::Date,
+        polars::datatypes::DataType::Duration(unit) => AtomType::Duration {
+            unit: hdv_time_unit(*unit),
+        },
+        polars::datatypes::DataType::Decimal(precision, scale) => AtomType::Decimal {
+            precision: precision.unwrap_or(38).try_into().ok()?,
+            scale: scale.unwrap_or(0).try_into().ok()?,
+        },
+        _ => return None,
+    })
+}
+/// Return [`None`] if a list contains nulls.
+fn atom_values(series: &Column, atom_type: &AtomType) -> Option<Vec<Option<AtomValue>>> {
+    Some(match atom_type {
+        AtomType::String => series
+            .str()
+            .unwrap()
+            .iter()
+            .map(|x| x.map(|x| x.into()).map(AtomValue::String))
+            .collect(),
+        AtomType::Bytes => series
+            .binary()
+            .unwrap()
+            .iter()
+            .map(|x| x.map(|x| x.into()).map(AtomValue::Bytes))
+            .collect(),
+        AtomType::U8 | AtomType::U16 | AtomType::U32 | AtomType::U64 => series
+            .cast(&polars::datatypes::DataType::UInt64)
+            .unwrap()
+            .u64()
+            .unwrap()
+            .iter()
+            .map(|x| x.map(AtomValue::U64))
+            .collect(),
+        AtomType::I8 | AtomType::I16 | AtomType::I32 | AtomType::I64 => series
+            .cast(&polars::datatypes::DataType::Int64)
+            .unwrap()
+            .i64()
+            .unwrap()
+            .iter()
+            .map(|x| x.map(AtomValue::I64))
+            .collect(),
+        AtomType::F32 => series
+            .cast(&polars::datatypes::DataType::Float32)
+            .unwrap()
+            .f32()
+            .unwrap()
+            .iter()
+            .map(|x| x.map(AtomValue::F32))
+            .collect(),
+        AtomType::F64 => series
+            .cast(&polars::datatypes::DataType::Float64)
+            .unwrap()
+            .f64()
+            .unwrap()
+            .iter()
+            .map(|x| x.map(AtomValue::F64))
+            .collect(),
+        AtomType::Bool => series
+            .cast(&polars::datatypes::DataType::Boolean)
+            .unwrap()
+            .bool()
+            .unwrap()
+            .iter()
+            .map(|x| x.map(AtomValue::Bool))
+            .collect(),
+        AtomType::Timestamp { .. } => series
+            .cast(&polars::datatypes::DataType::Int64)
+            .unwrap()
+            .i64()
+            .unwrap()
+            .iter()
+            .map(|x| x.map(AtomValue::Timestamp))
+            .collect(),
+        AtomType::Date => series
+            .cast(&polars::datatypes::DataType::Int32)
+            .unwrap()
+            .i32()
+            .unwrap()
+            .iter()
+            .map(|x| x.map(AtomValue::Date))
+            .collect(),
+        AtomType::Duration { .. } => series
+            .cast(&polars::datatypes::DataType::Int64)
+            .unwrap()
+            .i64()
+            .unwrap()
+            .iter()
+            .map(|x| x.map(AtomValue::Duration))
+            .collect(),
+        AtomType::Decimal { .. } => series
+            .decimal()
+            .unwrap()
+            .physical()
+            .iter()
+            .map(|x| x.map(AtomValue::Decimal))
+            .collect(),
+        &AtomType::List(item_type) => {
+            let mut column = vec![];
+            for items in series.list().unwrap() {
+                let Some(items) = items else {
+                    column.push(None);
+                    continue;
+                };
+                let items = atom_values(&items.into_column(), &item_type.atom_type())?;
+                let items = items.into_iter().collect::<Option<Vec<AtomValue>>>()?;
+                column.push(Some(AtomValue::List(item_type, items.into())));
+            }
+            column
+        }
+        AtomType::Map(..) | AtomType::U128 | AtomType::I128 | AtomType::Uuid => return None,
+    })
+}
+/// Return [`None`] if a column is a map, a 128-bit integer, a UUID or a timestamp with an unknown time zone.
 fn hdv_polars_read<'a>(
     rows: impl Iterator<Item = &'a ValueRow> + Clone,
     header: &[AtomScheme],
-) -> polars::frame::DataFrame {
+) -> Option<polars::frame::DataFrame> {
     let mut series_array = vec![];
     for (i, column_scheme) in header.iter().enumerate() {
         let mut column = vec![];
@@ -196,58 +271,237 @@ fn hdv_polars_read<'a>(
             let cell = row.atoms()[i].clone();
             column.push(cell);
         }
-        let series = match column_scheme.r#type {
-            AtomType::String => {
-                let column = column
-                    .into_iter()
-                    .map(|x| x.map(|x| x.string().map(|x| x.to_string()).unwrap()))
-                    .collect::<Vec<Option<String>>>();
-                Column::new(column_scheme.name.clone().into(), column)
-            }
-            AtomType::Bytes => {
-                let column = column
-                    .into_iter()
-                    .map(|x| x.map(|x| x.bytes().map(|x| x.to_vec()).unwrap()))
-                    .collect::<Vec<Option<Vec<u8>>>>();
-                Column::new(column_scheme.name.clone().into(), column)
-            }
-            AtomType::U64 => {
-                let column = column
-                    .into_iter()
-                    .map(|x| x.map(|x| x.u64().unwrap()))
-                    .collect::<Vec<Option<u64>>>();
-                Column::new(column_scheme.name.clone().into(), column)
-            }
-            AtomType::I64 => {
-                let column = column
-                    .into_iter()
-                    .map(|x| x.map(|x| x.i64().unwrap()))
-                    .collect::<Vec<Option<i64>>>();
-                Column::new(column_scheme.name.clone().into(), column)
-            }
-            AtomType::F32 => {
-                let column = column
-                    .into_iter()
-                    .map(|x| x.map(|x| x.f32().unwrap()))
-                    .collect::<Vec<Option<f32>>>();
-                Column::new(column_scheme.name.clone().into(), column)
-            }
-            AtomType::F64 => {
-                let column = column
-                    .into_iter()
-                    .map(|x| x.map(|x| x.f64().unwrap()))
-                    .collect::<Vec<Option<f64>>>();
-                Column::new(column_scheme.name.clone().into(), column)
-            }
-            AtomType::Bool => {
-                let column = column
-                    .into_iter()
-                    .map(|x| x.map(|x| x.bool().unwrap()))
-                    .collect::<Vec<Option<bool>>>();
-                Column::new(column_scheme.name.clone().into(), column)
-            }
-        };
+        let series = polars_column(
+            column_scheme.name.clone().into(),
+            &column_scheme.r#type,
+            column,
+        )?;
         series_array.push(series);
     }
-    polars::frame::DataFrame::new(series_array).unwrap()
+    Some(polars::frame::DataFrame::new(series_array).unwrap())
+}
+fn polars_column(
+    name: polars::prelude::PlSmallStr,
+    atom_type: &AtomType,
+    column: Vec<Option<AtomValue>>,
+) -> Option<Column> {
+    Some(match atom_type {
+        AtomType::String => {
+            let column = column
+                .into_iter()
+                .map(|x| x.map(|x| x.string().map(|x| x.to_string()).unwrap()))
+                .collect::<Vec<Option<String>>>();
+            Column::new(name, column)
+        }
+        AtomType::Bytes => {
+            let column = column
+                .into_iter()
+                .map(|x| x.map(|x| x.bytes().map(|x| x.to_vec()).unwrap()))
+                .collect::<Vec<Option<Vec<u8>>>>();
+            Column::new(name, column)
+        }
+        AtomType::U8 | AtomType::U16 | AtomType::U32 | AtomType::U64 => {
+            let column = column
+                .into_iter()
+                .map(|x| x.map(|x| x.u64().unwrap()))
+                .collect::<Vec<Option<u64>>>();
+            Column::new(name, column)
+                .cast(&polars_int_dtype(atom_type))
+                .unwrap()
+        }
+        AtomType::I8 | AtomType::I16 | AtomType::I32 | AtomType::I64 => {
+            let column = column
+                .into_iter()
+                .map(|x| x.map(|x| x.i64().unwrap()))
+                .collect::<Vec<Option<i64>>>();
+            Column::new(name, column)
+                .cast(&polars_int_dtype(atom_type))
+                .unwrap()
+        }
+        AtomType::F32 => {
+            let column = column
+                .into_iter()
+                .map(|x| x.map(|x| x.f32().unwrap()))
+                .collect::<Vec<Option<f32>>>();
+            Column::new(name, column)
+        }
+        AtomType::F64 => {
+            let column = column
+                .into_iter()
+                .map(|x| x.map(|x| x.f64().unwrap()))
+                .collect::<Vec<Option<f64>>>();
+            Column::new(name, column)
+        }
+        AtomType::Bool => {
+            let column = column
+                .into_iter()
+                .map(|x| x.map(|x| x.bool().unwrap()))
+                .collect::<Vec<Option<bool>>>();
+            Column::new(name, column)
+        }
+        AtomType::Timestamp { unit, tz } => {
+            let column = column
+                .into_iter()
+                .map(|x| x.map(|x| x.timestamp().unwrap()))
+                .collect::<Vec<Option<i64>>>();
+            let tz = tz.as_deref().map(polars::datatypes::TimeZone::from);
+            let dtype = polars::datatypes::DataType::Datetime(polars_time_unit(*unit), tz);
+            // Unknown time zones are rejected
+            Column::new(name, column).cast(&dtype).ok()?
+        }
+        AtomType::Date => {
+            let column = column
+                .into_iter()
+                .map(|x| x.map(|x| x.date().unwrap()))
+                .collect::<Vec<Option<i32>>>();
+            let dtype = polars::datatypes::DataType::Date;
+            Column::new(name, column).cast(&dtype).unwrap()
+        }
+        AtomType::Duration { unit } => {
+            let column = column
+                .into_iter()
+                .map(|x| x.map(|x| x.duration().unwrap()))
+                .collect::<Vec<Option<i64>>>();
+            let dtype = polars::datatypes::DataType::Duration(polars_time_unit(*unit));
+            Column::new(name, column).cast(&dtype).unwrap()
+        }
+        AtomType::Decimal { precision, scale } => {
+            let column = column.into_iter().map(|x| x.map(|x| x.decimal().unwrap()));
+            polars::prelude::Int128Chunked::from_iter_options(name, column)
+                .into_decimal(Some((*precision).into()), (*scale).into())
+                .ok()?
+                .into_series()
+                .into_column()
+        }
+        &AtomType::List(item_type) => {
+            let item_dtype = polars_column(
+                polars::prelude::PlSmallStr::EMPTY,
+                &item_type.atom_type(),
+                vec![],
+            )?
+            .dtype()
+            .clone();
+            let column = column
+                .into_iter()
+                .map(|x| {
+                    let Some(x) = x else {
+                        return Some(None);
+                    };
+                    let items = x.list().unwrap().iter().cloned().map(Some).collect();
+                    let items = polars_column(
+                        polars::prelude::PlSmallStr::EMPTY,
+                        &item_type.atom_type(),
+                        items,
+                    )?;
+                    Some(Some(items.take_materialized_series()))
+                })
+                .collect::<Option<Vec<Option<polars::prelude::Series>>>>()?;
+            // The item type is lost if every list is null
+            let dtype = polars::datatypes::DataType::List(Box::new(item_dtype));
+            Column::new(name, column).cast(&dtype).unwrap()
+        }
+        AtomType::Map(..) | AtomType::U128 | AtomType::I128 | AtomType::Uuid => return None,
+    })
+}
+fn polars_int_dtype(atom_type: &AtomType) -> polars::datatypes::DataType {
+    match atom_type {
+        AtomType::U8 => polars::datatypes::DataType::UInt8,
+        AtomType::U16 => polars::datatypes::DataType::UInt16,
+        AtomType::U32 => polars::datatypes::DataType::UInt32,
+        AtomType::U64 => polars::datatypes::DataType::UInt64,
+        AtomType::I8 => polars::datatypes::DataType::Int8,
+        AtomType::I16 => polars::datatypes::DataType::Int16,
+        AtomType::I32 => polars::datatypes::DataType::Int32,
+        AtomType::I64 => polars::datatypes::DataType::Int64,
+        _ => unreachable!(),
+    }
+}
+fn polars_time_unit(unit: TimeUnit) -> polars::prelude::TimeUnit {
+    match unit {
+        TimeUnit::Milliseconds => polars::prelude::TimeUnit::Milliseconds,
+        TimeUnit::Microseconds => polars::prelude::TimeUnit::Microseconds,
+        TimeUnit::Nanoseconds => polars::prelude::TimeUnit::Nanoseconds,
+    }
+}
+fn hdv_time_unit(unit: polars::prelude::TimeUnit) -> TimeUnit {
+    match unit {
+        polars::prelude::TimeUnit::Milliseconds => TimeUnit::Milliseconds,
+        polars::prelude::TimeUnit::Microseconds => TimeUnit::Microseconds,
+        polars::prelude::TimeUnit::Nanoseconds => TimeUnit::Nanoseconds,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use polars::prelude::NamedFrom;
+
+    use super::*;
+
+    fn round_trip(df: &polars::frame::DataFrame) -> polars::frame::DataFrame {
+        let mut buf = vec![];
+        hdv_bin_polars_write(&mut buf, df).unwrap();
+        hdv_bin_polars_read(buf.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn test_time_zone() {
+        let header = vec![AtomScheme {
+            name: "at".to_string(),
+            r#type: AtomType::Timestamp {
+                unit: TimeUnit::Microseconds,
+                tz: Some("UTC".into()),
+            },
+        }];
+        let mut buf = vec![];
+        let mut writer = HdvBinRawWriter::new(&mut buf, header);
+        writer
+            .write(&ValueRow::new(vec![Some(AtomValue::Timestamp(1))]))
+            .unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+        let df = hdv_bin_polars_read(buf.as_slice()).unwrap();
+        assert_eq!(
+            df.column("at").unwrap().dtype(),
+            &polars::datatypes::DataType::Datetime(
+                polars::prelude::TimeUnit::Microseconds,
+                Some("UTC".into()),
+            ),
+        );
+        assert_eq!(round_trip(&df), df);
+    }
+
+    #[test]
+    fn test_dtypes() {
+        let decimal = polars::prelude::Int128Chunked::from_iter_options(
+            "e".into(),
+            [Some(-12345), None].into_iter(),
+        )
+        .into_decimal(Some(10), 2)
+        .unwrap()
+        .into_series()
+        .into_column();
+        let df = polars::frame::DataFrame::new(vec![
+            Column::new("a".into(), [Some(1_u8), None]),
+            Column::new("b".into(), [1_u16, 2]),
+            Column::new("c".into(), [-1_i8, 2]),
+            Column::new("d".into(), [-1_i32, 2]),
+            decimal,
+        ])
+        .unwrap();
+        assert_eq!(round_trip(&df), df);
+    }
+
+    #[test]
+    fn test_narrow_list_items() {
+        let items = [
+            polars::prelude::Series::new("".into(), [1_i32, 2]),
+            polars::prelude::Series::new("".into(), [3_i32]),
+        ];
+        let df = polars::frame::DataFrame::new(vec![Column::new("a".into(), items)]).unwrap();
+        let df_ = round_trip(&df);
+        let dtype = polars::datatypes::DataType::List(Box::new(polars::datatypes::DataType::Int64));
+        assert_eq!(df_.column("a").unwrap().dtype(), &dtype);
+        let column = df.column("a").unwrap().cast(&dtype).unwrap();
+        assert_eq!(df_, polars::frame::DataFrame::new(vec![column]).unwrap());
+    }
 }
@@ -2,10 +2,12 @@ use std::marker::PhantomData;
 
 use crate::{
     format::{AtomScheme, AtomType, AtomValue, ValueRow},
-    serde::{HdvDeserialize, HdvScheme, HdvSerialize},
+    serde::{HdvDeserialize, HdvDeserializeSeed, HdvHeader, HdvScheme, HdvSerializeRow},
 };
 
-use super::{assert_atom_types, HdvShiftedHeader};
+use super::{check_atom_types, HdvReaderOptions, HdvShiftedHeader};
+
+mod iso8601;
 
 #[derive(Debug, Clone)]
 pub struct HdvTextWriterOptions {
@@ -14,7 +16,7 @@ pub struct HdvTextWriterOptions {
 #[derive(Debug)]
 pub struct HdvTextWriter<W, O> {
     options: HdvTextWriterOptions,
-    has_written_header: bool,
+    header: Option<Vec<AtomScheme>>,
     write: W,
     _object: PhantomData<O>,
 }
@@ -22,7 +24,7 @@ impl<W, V> HdvTextWriter<W, V> {
     pub fn new(write: W, options: HdvTextWriterOptions) -> Self {
         Self {
             options,
-            has_written_header: false,
+            header: None,
             write,
             _object: PhantomData,
         }
@@ -31,22 +33,24 @@ impl<W, V> HdvTextWriter<W, V> {
 impl<W, O> HdvTextWriter<W, O>
 where
     W: std::io::Write,
-    O: HdvSerialize + HdvScheme,
+    O: HdvSerializeRow + HdvHeader,
 {
     pub fn write(&mut self, object: &O) -> std::io::Result<()> {
-        if !self.has_written_header {
-            self.has_written_header = true;
-
-            let header = O::object_scheme();
-            let header = header.atom_schemes();
-            write_header(&mut self.write, &header, &self.options)?;
-        }
+        let header = match &self.header {
+            Some(header) => header,
+            None => {
+                let header = object.header();
+                write_header(&mut self.write, &header, &self.options)?;
+                self.header.insert(header)
+            }
+        };
 
         let mut atoms = vec![];
-        object.serialize(&mut atoms);
+        object.serialize_row(&mut atoms);
 
         let row = ValueRow::new(atoms);
-        write_row(&mut self.write, &row)?;
+        check_atom_types(header, &row)?;
+        write_row(&mut self.write, header, &row)?;
         Ok(())
     }
 
@@ -83,9 +87,9 @@ where
             write_header(&mut self.write, &self.header, &self.options)?;
         }
 
-        assert_atom_types(&self.header, row);
+        check_atom_types(&self.header, row)?;
 
-        write_row(&mut self.write, row)?;
+        write_row(&mut self.write, &self.header, row)?;
         Ok(())
     }
 
@@ -96,6 +100,7 @@ where
 
 #[derive(Debug)]
 pub struct HdvTextReader<R, O> {
+    options: HdvReaderOptions,
     shift_header: Option<HdvShiftedHeader>,
     read: R,
     buf: String,
@@ -104,7 +109,12 @@ pub struct HdvTextReader<R, O> {
 }
 impl<R, O> HdvTextReader<R, O> {
     pub fn new(read: R) -> Self {
+        Self::with_options(read, HdvReaderOptions::default())
+    }
+
+    pub fn with_options(read: R, options: HdvReaderOptions) -> Self {
         Self {
+            options,
             shift_header: None,
             read,
             buf: String::new(),
@@ -119,20 +129,35 @@ where
     O: HdvDeserialize + HdvScheme,
 {
     pub fn read(&mut self) -> std::io::Result<O> {
+        self.read_seed(&PhantomData)
+    }
+}
+impl<R, O> HdvTextReader<R, O>
+where
+    R: std::io::BufRead,
+{
+    /// Read an object of a scheme only known at runtime.
+    ///
+    /// The header is matched against the scheme of the seed of the first read.
+    pub fn read_seed<S>(&mut self, seed: &S) -> std::io::Result<O>
+    where
+        S: HdvDeserializeSeed<Value = O>,
+    {
         let Some(shift_header) = &self.shift_header else {
             let header = read_header(&mut self.read, &mut self.buf)?;
-            let shift_header = HdvShiftedHeader::new(header, &O::object_scheme())
-                .ok_or(std::io::ErrorKind::InvalidInput)?;
+            let shift_header = HdvShiftedHeader::new(header, &seed.object_scheme(), &self.options)?;
             self.shift_header = Some(shift_header);
 
-            return self.read();
+            return self.read_seed(seed);
         };
 
         let row = read_row(&mut self.read, shift_header.header(), &mut self.buf)?;
         self.atom_value_buf.clear();
-        shift_header.shift(row.atoms(), &mut self.atom_value_buf);
+        shift_header.shift(row.atoms(), &mut self.atom_value_buf)?;
 
-        let object = O::deserialize(&mut self.atom_value_buf.as_slice()).unwrap();
+        let object = seed
+            .deserialize(&mut self.atom_value_buf.as_slice())
+            .ok_or(std::io::ErrorKind::InvalidData)?;
         Ok(object)
     }
 }
@@ -193,7 +218,7 @@ where
     write.write_all(b"\n").unwrap();
     Ok(())
 }
-fn read_header<R>(read: &mut R, buf: &mut String) -> std::io::Result<Vec<AtomScheme>>
+pub(crate) fn read_header<R>(read: &mut R, buf: &mut String) -> std::io::Result<Vec<AtomScheme>>
 where
     R: std::io::BufRead,
 {
@@ -204,48 +229,136 @@ where
     Ok(header)
 }
 
-fn write_row<W>(write: &mut W, row: &ValueRow) -> std::io::Result<()>
+/// Lists are written as `[a;b;c]` and maps as `{a:1;b:2}`
+///
+/// Timestamps, dates and durations are written in ISO-8601.
+fn write_row<W>(write: &mut W, header: &[AtomScheme], row: &ValueRow) -> std::io::Result<()>
 where
     W: std::io::Write,
 {
-    for item in row.atoms() {
-        let Some(value) = item else {
-            write.write_all(b",")?;
-            continue;
-        };
-        match value {
-            AtomValue::String(x) => {
-                if x.contains(",")
-                    || x.contains("\"")
-                    || x.contains("\n")
-                    || x.trim_start().as_bytes() != x.as_bytes()
-                {
+    for (item, scheme) in row.atoms().iter().zip(header) {
+        if let Some(value) = item {
+            write_atom(write, value, &scheme.r#type)?;
+        }
+        write.write_all(b",")?;
+    }
+    write.write_all(b"\n")?;
+    Ok(())
+}
+/// Write a non-null value of a column of type `ty`
+pub(crate) fn write_atom<W>(write: &mut W, value: &AtomValue, ty: &AtomType) -> std::io::Result<()>
+where
+    W: std::io::Write,
+{
+    match value {
+        AtomValue::List(_, items) => {
+            write.write_all(b"[")?;
+            for (i, item) in items.iter().enumerate() {
+                if i != 0 {
+                    write.write_all(b";")?;
+                }
+                if item.string().is_some_and(|x| x.is_empty()) {
                     Err(std::io::ErrorKind::InvalidInput)?;
                 }
-                write.write_all(x.as_bytes())?;
-                write.write_all(b",")?;
-            }
-            AtomValue::Bytes(_) => {
-                Err(std::io::ErrorKind::InvalidInput)?;
+                write_scalar(write, item, "[;]")?;
             }
-            AtomValue::U64(x) => {
-                write.write_all(format!("{x},").as_bytes())?;
-            }
-            AtomValue::I64(x) => {
-                write.write_all(format!("{x},").as_bytes())?;
-            }
-            AtomValue::F32(x) => {
-                write.write_all(format!("{x},").as_bytes())?;
+            write.write_all(b"]")?;
+        }
+        AtomValue::Map(_, _, entries) => {
+            write.write_all(b"{")?;
+            for (i, (key, value)) in entries.iter().enumerate() {
+                if i != 0 {
+                    write.write_all(b";")?;
+                }
+                write_scalar(write, key, "{;:}")?;
+                write.write_all(b":")?;
+                write_scalar(write, value, "{;:}")?;
             }
-            AtomValue::F64(x) => {
-                write.write_all(format!("{x},").as_bytes())?;
+            write.write_all(b"}")?;
+        }
+        AtomValue::Timestamp(x) => {
+            let AtomType::Timestamp { unit, tz } = ty else {
+                Err(std::io::ErrorKind::InvalidInput)?
+            };
+            let text = iso8601::format_timestamp(*x, *unit, tz.is_some());
+            write.write_all(text.as_bytes())?;
+        }
+        AtomValue::Date(x) => {
+            let text = iso8601::format_date((*x).into());
+            write.write_all(text.as_bytes())?;
+        }
+        AtomValue::Duration(x) => {
+            let AtomType::Duration { unit } = ty else {
+                Err(std::io::ErrorKind::InvalidInput)?
+            };
+            let text = iso8601::format_duration(*x, *unit);
+            write.write_all(text.as_bytes())?;
+        }
+        AtomValue::Decimal(x) => {
+            let AtomType::Decimal { scale, .. } = ty else {
+                Err(std::io::ErrorKind::InvalidInput)?
+            };
+            write.write_all(format_decimal(*x, *scale).as_bytes())?;
+        }
+        _ => write_scalar(write, value, "")?,
+    }
+    Ok(())
+}
+/// Strings containing any of the chars in `delimiters` are rejected.
+///
+/// Empty strings are rejected as list items since `[]` is an empty list.
+fn write_scalar<W>(write: &mut W, value: &AtomValue, delimiters: &str) -> std::io::Result<()>
+where
+    W: std::io::Write,
+{
+    match value {
+        AtomValue::String(x) => {
+            if x.contains(",")
+                || x.contains("\"")
+                || x.contains("\n")
+                || x.trim_start().as_bytes() != x.as_bytes()
+            {
+                Err(std::io::ErrorKind::InvalidInput)?;
             }
-            AtomValue::Bool(x) => {
-                write.write_all(format!("{x},").as_bytes())?;
+            if x.contains(|c| delimiters.contains(c)) {
+                Err(std::io::ErrorKind::InvalidInput)?;
             }
+            write.write_all(x.as_bytes())?;
+        }
+        AtomValue::Bytes(_)
+        | AtomValue::List(..)
+        | AtomValue::Map(..)
+        | AtomValue::Timestamp(_)
+        | AtomValue::Date(_)
+        | AtomValue::Duration(_)
+        | AtomValue::Decimal(_) => {
+            Err(std::io::ErrorKind::InvalidInput)?;
+        }
+        AtomValue::U64(x) => {
+            write.write_all(format!("{x}").as_bytes())?;
+        }
+        AtomValue::I64(x) => {
+            write.write_all(format!("{x}").as_bytes())?;
+        }
+        AtomValue::F32(x) => {
+            write.write_all(format!("{x}").as_bytes())?;
+        }
+        AtomValue::F64(x) => {
+            write.write_all(format!("{x}").as_bytes())?;
+        }
+        AtomValue::Bool(x) => {
+            write.write_all(format!("{x}").as_bytes())?;
+        }
+        AtomValue::U128(x) => {
+            write.write_all(format!("{x}").as_bytes())?;
+        }
+        AtomValue::I128(x) => {
+            write.write_all(format!("{x}").as_bytes())?;
+        }
+        AtomValue::Uuid(x) => {
+            write.write_all(format_uuid(x).as_bytes())?;
         }
     }
-    write.write_all(b"\n")?;
     Ok(())
 }
 fn read_row<R>(
@@ -257,7 +370,9 @@ where
     R: std::io::BufRead,
 {
     buf.clear();
-    read.read_line(buf)?;
+    if read.read_line(buf)? == 0 {
+        Err(std::io::ErrorKind::UnexpectedEof)?;
+    }
     let items = buf.split(",");
     let zip = items.zip(atom_schemes.iter());
     let mut atoms = vec![];
@@ -266,45 +381,195 @@ where
             atoms.push(None);
             continue;
         }
-        let atom = match scheme.r#type {
-            AtomType::String => AtomValue::String(item.trim_start().into()),
-            AtomType::Bytes => return Err(std::io::ErrorKind::InvalidInput)?,
-            AtomType::U64 => AtomValue::U64(
-                item.trim()
-                    .parse()
-                    .map_err(|_| std::io::ErrorKind::InvalidInput)?,
-            ),
-            AtomType::I64 => AtomValue::I64(
-                item.trim()
-                    .parse()
-                    .map_err(|_| std::io::ErrorKind::InvalidInput)?,
-            ),
-            AtomType::F32 => AtomValue::F32(
-                item.trim()
-                    .parse()
-                    .map_err(|_| std::io::ErrorKind::InvalidInput)?,
-            ),
-            AtomType::F64 => AtomValue::F64(
-                item.trim()
-                    .parse()
-                    .map_err(|_| std::io::ErrorKind::InvalidInput)?,
-            ),
-            AtomType::Bool => AtomValue::Bool(
-                item.trim()
-                    .parse()
-                    .map_err(|_| std::io::ErrorKind::InvalidInput)?,
-            ),
+        let atom = match &scheme.r#type {
+            &AtomType::List(item_type) => {
+                let items = item
+                    .trim()
+                    .strip_prefix("[")
+                    .and_then(|x| x.strip_suffix("]"))
+                    .ok_or(std::io::ErrorKind::InvalidInput)?;
+                let items = if items.is_empty() {
+                    vec![]
+                } else {
+                    items
+                        .split(";")
+                        .map(|x| read_scalar(x, &item_type.atom_type()))
+                        .collect::<std::io::Result<_>>()?
+                };
+                AtomValue::List(item_type, items.into())
+            }
+            &AtomType::Map(key_type, value_type) => {
+                let entries = item
+                    .trim()
+                    .strip_prefix("{")
+                    .and_then(|x| x.strip_suffix("}"))
+                    .ok_or(std::io::ErrorKind::InvalidInput)?;
+                let entries = if entries.is_empty() {
+                    vec![]
+                } else {
+                    entries
+                        .split(";")
+                        .map(|x| {
+                            let (key, value) =
+                                x.split_once(":").ok_or(std::io::ErrorKind::InvalidInput)?;
+                            let key = read_scalar(key, &key_type.atom_type())?;
+                            let value = read_scalar(value, &value_type.atom_type())?;
+                            Ok((key, value))
+                        })
+                        .collect::<std::io::Result<_>>()?
+                };
+                AtomValue::Map(key_type, value_type, entries.into())
+            }
+            ty => read_scalar(item, ty)?,
         };
         atoms.push(Some(atom));
     }
     Ok(ValueRow::new(atoms))
 }
+fn read_scalar(item: &str, ty: &AtomType) -> std::io::Result<AtomValue> {
+    Ok(match ty {
+        AtomType::String => AtomValue::String(item.trim_start().into()),
+        AtomType::Bytes | AtomType::List(_) | AtomType::Map(..) => {
+            return Err(std::io::ErrorKind::InvalidInput)?
+        }
+        AtomType::U64 => AtomValue::U64(
+            item.trim()
+                .parse()
+                .map_err(|_| std::io::ErrorKind::InvalidInput)?,
+        ),
+        AtomType::I64 => AtomValue::I64(
+            item.trim()
+                .parse()
+                .map_err(|_| std::io::ErrorKind::InvalidInput)?,
+        ),
+        AtomType::F32 => AtomValue::F32(
+            item.trim()
+                .parse()
+                .map_err(|_| std::io::ErrorKind::InvalidInput)?,
+        ),
+        AtomType::F64 => AtomValue::F64(
+            item.trim()
+                .parse()
+                .map_err(|_| std::io::ErrorKind::InvalidInput)?,
+        ),
+        AtomType::Bool => AtomValue::Bool(
+            item.trim()
+                .parse()
+                .map_err(|_| std::io::ErrorKind::InvalidInput)?,
+        ),
+        AtomType::Timestamp { unit, .. } => AtomValue::Timestamp(
+            iso8601::parse_timestamp(item.trim(), *unit).ok_or(std::io::ErrorKind::InvalidInput)?,
+        ),
+        AtomType::Date => {
+            let days = iso8601::parse_date(item.trim()).ok_or(std::io::ErrorKind::InvalidInput)?;
+            AtomValue::Date(
+                days.try_into()
+                    .map_err(|_| std::io::ErrorKind::InvalidInput)?,
+            )
+        }
+        AtomType::Duration { unit } => AtomValue::Duration(
+            iso8601::parse_duration(item.trim(), *unit).ok_or(std::io::ErrorKind::InvalidInput)?,
+        ),
+        AtomType::U128 => AtomValue::U128(
+            item.trim()
+                .parse()
+                .map_err(|_| std::io::ErrorKind::InvalidInput)?,
+        ),
+        AtomType::I128 => AtomValue::I128(
+            item.trim()
+                .parse()
+                .map_err(|_| std::io::ErrorKind::InvalidInput)?,
+        ),
+        AtomType::Uuid => {
+            AtomValue::Uuid(parse_uuid(item.trim()).ok_or(std::io::ErrorKind::InvalidInput)?)
+        }
+        AtomType::U8 | AtomType::U16 | AtomType::U32 => {
+            let value = read_scalar(item, &AtomType::U64)?;
+            if !ty.is_type_of(&value) {
+                Err(std::io::ErrorKind::InvalidInput)?;
+            }
+            value
+        }
+        AtomType::I8 | AtomType::I16 | AtomType::I32 => {
+            let value = read_scalar(item, &AtomType::I64)?;
+            if !ty.is_type_of(&value) {
+                Err(std::io::ErrorKind::InvalidInput)?;
+            }
+            value
+        }
+        AtomType::Decimal { scale, .. } => {
+            let value = AtomValue::Decimal(
+                parse_decimal(item.trim(), *scale).ok_or(std::io::ErrorKind::InvalidInput)?,
+            );
+            if !ty.is_type_of(&value) {
+                Err(std::io::ErrorKind::InvalidInput)?;
+            }
+            value
+        }
+    })
+}
+/// `mantissa` with a decimal point before the last `scale` digits
+fn format_decimal(mantissa: i128, scale: u8) -> String {
+    let sign = if mantissa < 0 { "-" } else { "" };
+    let scale = usize::from(scale);
+    let digits = format!("{:0width$}", mantissa.unsigned_abs(), width = scale + 1);
+    if scale == 0 {
+        return format!("{sign}{digits}");
+    }
+    let (int, fraction) = digits.split_at(digits.len() - scale);
+    format!("{sign}{int}.{fraction}")
+}
+/// Return [`None`] if there are more than `scale` digits after the decimal point.
+fn parse_decimal(text: &str, scale: u8) -> Option<i128> {
+    let (sign, text) = match text.strip_prefix('-') {
+        Some(text) => (-1, text),
+        None => (1, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (int, fraction) = text.split_once('.').unwrap_or((text, ""));
+    let scale = usize::from(scale);
+    if int.is_empty()
+        || scale < fraction.len()
+        || !int
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|x| x.is_ascii_digit())
+    {
+        return None;
+    }
+    let digits = format!("{int}{fraction:0<scale$}");
+    let mantissa: i128 = digits.parse().ok()?;
+    Some(sign * mantissa)
+}
+/// `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx` in lowercase hex
+fn format_uuid(bytes: &[u8; 16]) -> String {
+    let mut text = String::with_capacity(36);
+    for (i, byte) in bytes.iter().enumerate() {
+        if [4, 6, 8, 10].contains(&i) {
+            text.push('-');
+        }
+        text.push_str(&format!("{byte:02x}"));
+    }
+    text
+}
+fn parse_uuid(text: &str) -> Option<[u8; 16]> {
+    let text = text.as_bytes();
+    if text.len() != 36 || [8, 13, 18, 23].iter().any(|&i| text[i] != b'-') {
+        return None;
+    }
+    let mut digits = text.iter().filter(|&&x| x != b'-');
+    let mut bytes = [0; 16];
+    for byte in &mut bytes {
+        let mut hex = || (*digits.next()? as char).to_digit(16);
+        *byte = (hex()? << 4 | hex()?) as u8;
+    }
+    Some(bytes)
+}
 
 #[cfg(test)]
 mod tests {
     use crate::{
         format::{AtomType, AtomValue},
-        serde::{FieldScheme, FieldType, ObjectScheme},
+        serde::{FieldScheme, FieldType, HdvSerialize, ObjectScheme},
     };
 
     use super::*;
@@ -318,18 +583,24 @@ mod tests {
         }
         impl HdvScheme for A {
             fn object_scheme() -> ObjectScheme {
-                ObjectScheme {
-                    fields: vec![
-                        FieldScheme {
-                            name: "a".to_string(),
-                            ty: FieldType::Atom(AtomType::I64),
-                        },
-                        FieldScheme {
-                            name: "b".to_string(),
-                            ty: FieldType::Atom(AtomType::F64),
-                        },
-                    ],
-                }
+                ObjectScheme::new(vec![
+                    FieldScheme {
+                        name: "a".to_string(),
+                        ty: FieldType::Atom(AtomType::I64),
+                        nullable: false,
+                        default: None,
+                        aliases: vec![],
+                        flatten: false,
+                    },
+                    FieldScheme {
+                        name: "b".to_string(),
+                        ty: FieldType::Atom(AtomType::F64),
+                        nullable: false,
+                        default: None,
+                        aliases: vec![],
+                        flatten: false,
+                    },
+                ])
             }
         }
         impl HdvSerialize for A {
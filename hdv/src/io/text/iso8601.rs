@@ -0,0 +1,191 @@
+//! ISO-8601 text of timestamps, dates and durations
+
+use crate::format::TimeUnit;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// `YYYY-MM-DD`
+pub fn format_date(days: i64) -> String {
+    let (year, month, day) = civil_from_days(days);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+pub fn parse_date(text: &str) -> Option<i64> {
+    let (sign, rest) = match text.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, text),
+    };
+    let mut parts = rest.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    if 1_000_000 < year {
+        return None;
+    }
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    let year = sign * year;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    (civil_from_days(days) == (year, month, day)).then_some(days)
+}
+
+/// `YYYY-MM-DDTHH:MM:SS.fff` followed by `Z` if `is_utc`
+///
+/// The fraction has as many digits as the unit.
+pub fn format_timestamp(value: i64, unit: TimeUnit, is_utc: bool) -> String {
+    let seconds = value.div_euclid(unit.per_second());
+    let fraction = value.rem_euclid(unit.per_second());
+    let date = format_date(seconds.div_euclid(SECONDS_PER_DAY));
+    let time = seconds.rem_euclid(SECONDS_PER_DAY);
+    let (hour, minute, second) = (time / 3600, time / 60 % 60, time % 60);
+    let width = unit.digits();
+    let zone = if is_utc { "Z" } else { "" };
+    format!("{date}T{hour:02}:{minute:02}:{second:02}.{fraction:0width$}{zone}")
+}
+/// Timestamps with a `Z` or `±HH:MM` suffix are converted to UTC.
+///
+/// Return [`None`] if the fraction is finer than the unit.
+pub fn parse_timestamp(text: &str, unit: TimeUnit) -> Option<i64> {
+    let (date, time) = text.split_once('T')?;
+    let days = parse_date(date)?;
+    let (time, offset) = if let Some(time) = time.strip_suffix('Z') {
+        (time, 0)
+    } else if let Some(i) = time.rfind(['+', '-']) {
+        let (time, offset) = time.split_at(i);
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let (hour, minute) = offset[1..].split_once(':')?;
+        let hour: i64 = hour.parse().ok()?;
+        let minute: i64 = minute.parse().ok()?;
+        if 24 <= hour || 60 <= minute {
+            return None;
+        }
+        (time, sign * (hour * 3600 + minute * 60))
+    } else {
+        (time, 0)
+    };
+    let (time, fraction) = match time.split_once('.') {
+        Some((time, fraction)) => (time, parse_fraction(fraction, unit)?),
+        None => (time, 0),
+    };
+    let mut parts = time.splitn(3, ':');
+    let hour: i64 = parts.next()?.parse().ok()?;
+    let minute: i64 = parts.next()?.parse().ok()?;
+    let second: i64 = parts.next()?.parse().ok()?;
+    if 24 <= hour || 60 <= minute || 60 <= second {
+        return None;
+    }
+    let seconds = days
+        .checked_mul(SECONDS_PER_DAY)?
+        .checked_add(hour * 3600 + minute * 60 + second - offset)?;
+    seconds
+        .checked_mul(unit.per_second())?
+        .checked_add(fraction)
+}
+
+/// `PTS.fffS` with a leading `-` if negative
+pub fn format_duration(value: i64, unit: TimeUnit) -> String {
+    let sign = if value < 0 { "-" } else { "" };
+    let value = value.unsigned_abs();
+    let per_second = unit.per_second() as u64;
+    let (seconds, fraction) = (value / per_second, value % per_second);
+    let width = unit.digits();
+    format!("{sign}PT{seconds}.{fraction:0width$}S")
+}
+pub fn parse_duration(text: &str, unit: TimeUnit) -> Option<i64> {
+    let (sign, rest) = match text.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, text),
+    };
+    let rest = rest.strip_prefix("PT")?.strip_suffix('S')?;
+    let (seconds, fraction) = match rest.split_once('.') {
+        Some((seconds, fraction)) => (seconds, parse_fraction(fraction, unit)?),
+        None => (rest, 0),
+    };
+    let seconds: i64 = seconds.parse().ok()?;
+    seconds
+        .checked_mul(sign * unit.per_second())?
+        .checked_add(sign * fraction)
+}
+
+/// Digits after the decimal point in the unit
+fn parse_fraction(text: &str, unit: TimeUnit) -> Option<i64> {
+    if unit.digits() < text.len() || !text.bytes().all(|x| x.is_ascii_digit()) {
+        return None;
+    }
+    let padding = unit.digits() - text.len();
+    let value: i64 = if text.is_empty() {
+        0
+    } else {
+        text.parse().ok()?
+    };
+    Some(value * 10_i64.pow(padding as u32))
+}
+
+/// <https://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let month_from_march = if 2 < month { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month_from_march + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+/// <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    } as u32;
+    let year = year_of_era + era * 400;
+    let year = if month <= 2 { year + 1 } else { year };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iso8601() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(-1), "1969-12-31");
+        assert_eq!(format_date(19782), "2024-02-29");
+        assert_eq!(parse_date("2024-02-29"), Some(19782));
+        assert_eq!(parse_date("2023-02-29"), None);
+
+        let ms = TimeUnit::Milliseconds;
+        assert_eq!(format_timestamp(-1, ms, true), "1969-12-31T23:59:59.999Z");
+        assert_eq!(parse_timestamp("1969-12-31T23:59:59.999Z", ms), Some(-1));
+        assert_eq!(
+            parse_timestamp("1970-01-01T01:00:00.5+01:00", ms),
+            Some(500)
+        );
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00", ms), Some(0));
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00.0001", ms), None);
+        let ns = TimeUnit::Nanoseconds;
+        assert_eq!(
+            format_timestamp(1, ns, false),
+            "1970-01-01T00:00:00.000000001"
+        );
+
+        assert_eq!(format_duration(-1500, ms), "-PT1.500S");
+        assert_eq!(parse_duration("-PT1.5S", ms), Some(-1500));
+        assert_eq!(parse_duration("PT2S", ms), Some(2000));
+        assert_eq!(format_duration(i64::MIN, ns), "-PT9223372036.854775808S");
+        assert_eq!(
+            parse_duration("-PT9223372036.854775808S", ns),
+            Some(i64::MIN)
+        );
+    }
+}
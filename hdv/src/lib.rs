@@ -1,3 +1,7 @@
+pub mod codegen;
+pub mod dynamic;
+pub mod export;
 pub mod format;
 pub mod io;
 pub mod serde;
+pub mod serde_bridge;
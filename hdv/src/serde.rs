@@ -1,29 +1,375 @@
-use crate::format::{AtomScheme, AtomType, AtomValue};
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::format::{AtomScheme, AtomType, AtomValue, AtomValueRef, ItemType, TimeUnit};
 
 pub trait HdvScheme {
     fn object_scheme() -> ObjectScheme;
+
+    /// Type of a field holding `Self`
+    fn field_type() -> FieldType {
+        FieldType::Object(Self::object_scheme())
+    }
+}
+
+/// Columns written by the typed writers, taken from the first object
+///
+/// Besides every [`HdvScheme`], records whose schemes are only known at runtime implement it.
+pub trait HdvHeader {
+    fn header(&self) -> Vec<AtomScheme>;
+}
+impl<T: HdvScheme + ?Sized> HdvHeader for T {
+    fn header(&self) -> Vec<AtomScheme> {
+        T::object_scheme().atom_schemes()
+    }
+}
+
+/// Values of the rows written by the typed writers
+///
+/// Besides every [`HdvSerialize`], records whose schemes are only known at runtime implement it.
+/// Records do not implement [`HdvSerialize`] since their widths are unknown without a value,
+/// so they cannot be nested in other objects.
+pub trait HdvSerializeRow {
+    fn serialize_row(&self, values: &mut Vec<Option<AtomValue>>);
+}
+impl<T: HdvSerialize + ?Sized> HdvSerializeRow for T {
+    fn serialize_row(&self, values: &mut Vec<Option<AtomValue>>) {
+        self.serialize(values);
+    }
 }
 
 pub trait HdvSerialize {
     fn serialize(&self, values: &mut Vec<Option<AtomValue>>);
     fn fill_nulls(values: &mut Vec<Option<AtomValue>>);
+
+    /// Push the values of a field of `Option<Self>`
+    ///
+    /// Objects write their validity columns with [`serialize_nullable_object`] by default.
+    /// Atoms write no validity columns with [`serialize_nullable_atom`].
+    fn serialize_nullable(value: Option<&Self>, values: &mut Vec<Option<AtomValue>>) {
+        serialize_nullable_object(value, values);
+    }
+    /// Push the nulls of a field of `Option<Self>`
+    fn fill_nullable_nulls(values: &mut Vec<Option<AtomValue>>) {
+        fill_nullable_object_nulls::<Self>(values);
+    }
 }
 
 pub trait HdvDeserialize: Sized {
     fn deserialize(values: &mut &[Option<AtomValue>]) -> Option<Self>;
+
+    /// Take the value of a field of `Option<Self>`
+    ///
+    /// Return [`None`] if the field is present but not a valid `Self`.
+    ///
+    /// Objects read their validity columns with [`deserialize_nullable_object`] by default.
+    /// Atoms read no validity columns with [`deserialize_nullable_atom`].
+    fn deserialize_nullable(values: &mut &[Option<AtomValue>]) -> Option<Option<Self>> {
+        deserialize_nullable_object(values)
+    }
+}
+
+/// Deserialize objects whose schemes are only known at runtime
+///
+/// The typed readers take it in place of [`HdvScheme`] and [`HdvDeserialize`] of the object type.
+pub trait HdvDeserializeSeed {
+    type Value;
+    fn object_scheme(&self) -> ObjectScheme;
+    fn deserialize(&self, values: &mut &[Option<AtomValue>]) -> Option<Self::Value>;
+}
+impl<T: HdvScheme + HdvDeserialize> HdvDeserializeSeed for std::marker::PhantomData<T> {
+    type Value = T;
+    fn object_scheme(&self) -> ObjectScheme {
+        T::object_scheme()
+    }
+    fn deserialize(&self, values: &mut &[Option<AtomValue>]) -> Option<T> {
+        T::deserialize(values)
+    }
+}
+
+/// Deserialize strings and bytes without copying them out of the row buffer of a reader
+pub trait HdvDeserializeBorrowed {
+    /// `Self` with its lifetimes replaced by `'a`
+    type Borrowed<'a>;
+    fn deserialize_borrowed<'a>(
+        values: &mut &[Option<AtomValueRef<'a>>],
+    ) -> Option<Self::Borrowed<'a>>;
+
+    /// Take the value of a field of `Option<Self>`
+    ///
+    /// Return [`None`] if the field is present but not a valid `Self`.
+    ///
+    /// Objects read their validity columns with [`deserialize_borrowed_nullable_object`] by default.
+    fn deserialize_borrowed_nullable<'a>(
+        values: &mut &[Option<AtomValueRef<'a>>],
+    ) -> Option<Option<Self::Borrowed<'a>>> {
+        deserialize_borrowed_nullable_object::<Self>(values)
+    }
+}
+
+/// [`HdvSerialize::serialize_nullable`] of atoms
+pub fn serialize_nullable_atom<T: HdvSerialize + ?Sized>(
+    value: Option<&T>,
+    values: &mut Vec<Option<AtomValue>>,
+) {
+    match value {
+        Some(x) => x.serialize(values),
+        None => T::fill_nulls(values),
+    }
+}
+/// [`HdvSerialize::fill_nullable_nulls`] of atoms
+pub fn fill_nullable_atom_nulls<T: HdvSerialize + ?Sized>(values: &mut Vec<Option<AtomValue>>) {
+    T::fill_nulls(values);
+}
+/// [`HdvDeserialize::deserialize_nullable`] of atoms
+pub fn deserialize_nullable_atom<T: HdvDeserialize>(
+    values: &mut &[Option<AtomValue>],
+) -> Option<Option<T>> {
+    if values.first()?.is_none() {
+        *values = &values[1..];
+        return Some(None);
+    }
+    T::deserialize(values).map(Some)
+}
+/// [`HdvDeserializeBorrowed::deserialize_borrowed_nullable`] of atoms
+pub fn deserialize_borrowed_nullable_atom<'a, T: HdvDeserializeBorrowed + ?Sized>(
+    values: &mut &[Option<AtomValueRef<'a>>],
+) -> Option<Option<T::Borrowed<'a>>> {
+    if values.first()?.is_none() {
+        *values = &values[1..];
+        return Some(None);
+    }
+    T::deserialize_borrowed(values).map(Some)
+}
+
+/// [`HdvSerialize::serialize_nullable`] of objects
+///
+/// The validity column is `true` for [`Some`] and `false` for [`None`].
+pub fn serialize_nullable_object<T: HdvSerialize + ?Sized>(
+    value: Option<&T>,
+    values: &mut Vec<Option<AtomValue>>,
+) {
+    values.push(Some(AtomValue::Bool(value.is_some())));
+    match value {
+        Some(x) => x.serialize(values),
+        None => T::fill_nulls(values),
+    }
+}
+/// [`HdvSerialize::fill_nullable_nulls`] of objects
+pub fn fill_nullable_object_nulls<T: HdvSerialize + ?Sized>(values: &mut Vec<Option<AtomValue>>) {
+    values.push(None);
+    T::fill_nulls(values);
+}
+/// [`HdvDeserialize::deserialize_nullable`] of objects
+///
+/// The object is null unless its validity column is `true`, in which case it must be a valid `T`.
+pub fn deserialize_nullable_object<T: HdvDeserialize>(
+    values: &mut &[Option<AtomValue>],
+) -> Option<Option<T>> {
+    let is_valid = values.first()?.as_ref().and_then(|x| x.bool());
+    *values = &values[1..];
+    let value = T::deserialize(values);
+    match is_valid {
+        Some(true) => value.map(Some),
+        _ => Some(None),
+    }
+}
+/// [`HdvDeserializeBorrowed::deserialize_borrowed_nullable`] of objects
+pub fn deserialize_borrowed_nullable_object<'a, T: HdvDeserializeBorrowed + ?Sized>(
+    values: &mut &[Option<AtomValueRef<'a>>],
+) -> Option<Option<T::Borrowed<'a>>> {
+    let is_valid = values.first()?.as_ref().and_then(|x| x.bool());
+    *values = &values[1..];
+    let value = T::deserialize_borrowed(values);
+    match is_valid {
+        Some(true) => value.map(Some),
+        _ => Some(None),
+    }
+}
+
+/// Write a record of the values of all columns of its header
+macro_rules! impl_hdv_serialize_record {
+    ($ty:ty) => {
+        impl $crate::serde::HdvSerializeRow for $ty {
+            fn serialize_row(&self, values: &mut Vec<Option<$crate::format::AtomValue>>) {
+                values.extend(self.values.iter().cloned());
+            }
+        }
+    };
 }
+pub(crate) use impl_hdv_serialize_record;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ObjectScheme {
     pub fields: Vec<FieldScheme>,
+    /// Joins the names of object fields with the names of their nested fields
+    pub separator: String,
 }
 impl ObjectScheme {
+    /// Separator of the schemes built by [`Self::new`]
+    pub const DEFAULT_SEPARATOR: &str = ".";
+
+    pub fn new(fields: Vec<FieldScheme>) -> Self {
+        Self {
+            fields,
+            separator: Self::DEFAULT_SEPARATOR.to_string(),
+        }
+    }
+
+    /// Columns of the atom fields in order
+    ///
+    /// A nullable object field is preceded by its validity column of [`AtomType::Bool`] named after the field.
     pub fn atom_schemes(&self) -> Vec<AtomScheme> {
         let mut atoms = vec![];
+        self.push_atom_schemes(None, &mut atoms);
+        atoms
+    }
+    /// `prefix` is the full name of the parent field and the separator following it.
+    fn push_atom_schemes(&self, prefix: Option<(&str, &str)>, atoms: &mut Vec<AtomScheme>) {
         for field in &self.fields {
-            atoms.extend(field.atom_schemes().into_iter());
+            self.push_field_atom_schemes(field, prefix, atoms);
+        }
+    }
+    /// `field` is one of the fields of this object.
+    fn push_field_atom_schemes(
+        &self,
+        field: &FieldScheme,
+        prefix: Option<(&str, &str)>,
+        atoms: &mut Vec<AtomScheme>,
+    ) {
+        let name = match prefix {
+            Some((prefix, separator)) => format!("{prefix}{separator}{}", field.name),
+            None => field.name.clone(),
+        };
+        match &field.ty {
+            FieldType::Atom(x) => atoms.push(AtomScheme {
+                name,
+                r#type: x.clone(),
+            }),
+            FieldType::Object(object) => {
+                if field.nullable {
+                    atoms.push(AtomScheme {
+                        name: name.clone(),
+                        r#type: AtomType::Bool,
+                    });
+                }
+                if field.flatten {
+                    object.push_atom_schemes(prefix, atoms);
+                } else {
+                    object.push_atom_schemes(Some((&name, &self.separator)), atoms);
+                }
+            }
+        }
+    }
+
+    /// Rebuild the nested fields from the columns of a header, the inverse of [`Self::atom_schemes`]
+    ///
+    /// Names are split by [`Self::DEFAULT_SEPARATOR`].
+    /// A [`AtomType::Bool`] column `a` directly followed by columns `a.*` is the validity column of the nullable object `a`.
+    /// Atom fields are nullable since headers carry no nullability.
+    ///
+    /// Return an error of [`std::io::ErrorKind::InvalidData`] if the paths of the columns conflict,
+    /// like `a` and `a.b`, or the columns of an object are not contiguous.
+    pub fn from_atom_schemes(atom_schemes: &[AtomScheme]) -> std::io::Result<Self> {
+        let separator = Self::DEFAULT_SEPARATOR;
+        let mut paths = vec![];
+        for atom in atom_schemes {
+            let path = atom.name.split(separator).collect::<Vec<_>>();
+            if path.iter().any(|x| x.is_empty()) {
+                let message = format!("column `{}` has an empty path segment", atom.name);
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    message,
+                ));
+            }
+            paths.push((path, atom));
+        }
+        Self::from_paths(&paths, 0, separator)
+    }
+    /// `paths` share the same first `depth` segments.
+    fn from_paths(
+        paths: &[(Vec<&str>, &AtomScheme)],
+        depth: usize,
+        separator: &str,
+    ) -> std::io::Result<Self> {
+        let invalid_data =
+            |message: String| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+        let mut fields = vec![];
+        let mut seen = std::collections::HashSet::new();
+        let mut start = 0;
+        while start < paths.len() {
+            let name = paths[start].0[depth];
+            let end = start
+                + paths[start..]
+                    .iter()
+                    .take_while(|(path, _)| path[depth] == name)
+                    .count();
+            let group = &paths[start..end];
+            start = end;
+            let full_name = group[0].0[..=depth].join(separator);
+            if !seen.insert(name) {
+                let message = format!("columns of `{full_name}` are not contiguous");
+                return Err(invalid_data(message));
+            }
+            let (head, atom) = &group[0];
+            let is_leaf = |path: &Vec<&str>| path.len() == depth + 1;
+            if let Some((_, other)) = group[1..].iter().find(|(path, _)| is_leaf(path)) {
+                let message = match other.name == atom.name {
+                    true => format!("column `{}` is defined more than once", atom.name),
+                    false => format!("column `{}` conflicts with `{}`", other.name, atom.name),
+                };
+                return Err(invalid_data(message));
+            }
+            let (ty, nullable) = match (is_leaf(head), group.len()) {
+                (true, 1) => (FieldType::Atom(atom.r#type.clone()), true),
+                (true, _) => {
+                    if atom.r#type != AtomType::Bool {
+                        let message = format!(
+                            "column `{}` conflicts with `{}`",
+                            atom.name, group[1].1.name
+                        );
+                        return Err(invalid_data(message));
+                    }
+                    let object = Self::from_paths(&group[1..], depth + 1, separator)?;
+                    (FieldType::Object(object), true)
+                }
+                (false, _) => {
+                    let object = Self::from_paths(group, depth + 1, separator)?;
+                    (FieldType::Object(object), false)
+                }
+            };
+            fields.push(FieldScheme {
+                name: name.to_string(),
+                ty,
+                nullable,
+                default: None,
+                aliases: vec![],
+                flatten: false,
+            });
+        }
+        Ok(Self {
+            fields,
+            separator: separator.to_string(),
+        })
+    }
+
+    /// # Panic
+    ///
+    /// If two columns share the same name.
+    pub fn assert_unique_names(&self) {
+        let mut names = std::collections::HashSet::new();
+        for atom in self.atom_schemes() {
+            assert!(
+                names.insert(atom.name.clone()),
+                "column `{}` is defined more than once",
+                atom.name
+            );
         }
-        atoms
     }
 
     pub fn atom_types(&self, types: &mut Vec<AtomType>) {
@@ -32,48 +378,650 @@ impl ObjectScheme {
         }
     }
 }
+impl Default for ObjectScheme {
+    fn default() -> Self {
+        Self::new(vec![])
+    }
+}
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct FieldScheme {
     pub name: String,
     pub ty: FieldType,
+    /// Whether the field accepts null values.
+    ///
+    /// Columns of a nullable field missing from a file are read as nulls.
+    pub nullable: bool,
+    /// Value of an atom field whose column is missing from a file.
+    ///
+    /// For a nullable object field, the value of its validity column.
+    pub default: Option<AtomValue>,
+    /// Former names of the field.
+    ///
+    /// A file without the column of the field is read from the column of the first matching alias.
+    pub aliases: Vec<String>,
+    /// Whether the nested fields of an object field are named without the name of this field
+    pub flatten: bool,
 }
 impl FieldScheme {
+    /// Columns of this field as the only field of an object of [`ObjectScheme::DEFAULT_SEPARATOR`]
     pub fn atom_schemes(&self) -> Vec<AtomScheme> {
-        let post_atoms = match &self.ty {
-            FieldType::Atom(x) => {
-                return vec![AtomScheme {
-                    name: self.name.clone(),
-                    r#type: *x,
-                }]
-            }
-            FieldType::Object(object) => object.atom_schemes(),
-        };
         let mut atoms = vec![];
-        for post_atom in &post_atoms {
-            let name = format!("{}.{}", self.name, post_atom.name);
-            atoms.push(AtomScheme {
-                name,
-                r#type: post_atom.r#type,
-            });
-        }
+        ObjectScheme::default().push_field_atom_schemes(self, None, &mut atoms);
         atoms
     }
 
+    /// Take the defaults of the atom fields from the serialized default value of this field.
+    pub fn set_defaults(&mut self, defaults: &mut impl Iterator<Item = Option<AtomValue>>) {
+        match &mut self.ty {
+            FieldType::Atom(_) => self.default = defaults.next().flatten(),
+            FieldType::Object(object) => {
+                if self.nullable {
+                    self.default = defaults.next().flatten();
+                }
+                for field in &mut object.fields {
+                    field.set_defaults(defaults);
+                }
+            }
+        }
+    }
+
+    /// Mark the atom fields within this field nullable, keeping the validity columns of objects.
+    ///
+    /// The fields of enum variants are null unless their variant is active.
+    pub fn set_atoms_nullable(&mut self) {
+        match &mut self.ty {
+            FieldType::Atom(_) => self.nullable = true,
+            FieldType::Object(object) => {
+                for field in &mut object.fields {
+                    field.set_atoms_nullable();
+                }
+            }
+        }
+    }
+
     pub fn atom_types(&self, types: &mut Vec<AtomType>) {
         match &self.ty {
-            FieldType::Atom(x) => types.push(*x),
-            FieldType::Object(object) => object.atom_types(types),
+            FieldType::Atom(x) => types.push(x.clone()),
+            FieldType::Object(object) => {
+                if self.nullable {
+                    types.push(AtomType::Bool);
+                }
+                object.atom_types(types);
+            }
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum FieldType {
     Atom(AtomType),
     Object(ObjectScheme),
 }
 
+/// Type stored in a single column
+pub trait HdvAtom: Sized {
+    fn atom_type() -> AtomType;
+    fn to_atom_value(&self) -> AtomValue;
+    /// Return [`None`] if the value is of another type or out of range.
+    fn from_atom_value(value: &AtomValue) -> Option<Self>;
+}
+macro_rules! impl_hdv_atom {
+    ($ty:ty, $arm:ident, $get:ident, |$x:ident| $to:expr, |$v:ident| $from:expr) => {
+        impl HdvAtom for $ty {
+            fn atom_type() -> AtomType {
+                AtomType::$arm
+            }
+            fn to_atom_value(&self) -> AtomValue {
+                let $x = self;
+                AtomValue::$arm($to)
+            }
+            fn from_atom_value(value: &AtomValue) -> Option<Self> {
+                let $v = value.$get()?;
+                $from
+            }
+        }
+        impl_hdv_atom_traits!($ty);
+    };
+}
+/// Store an [`HdvAtom`] as a single column
+macro_rules! impl_hdv_atom_traits {
+    ($ty:ty) => {
+        impl HdvScheme for $ty {
+            fn object_scheme() -> ObjectScheme {
+                single_column_scheme(Self::field_type())
+            }
+            fn field_type() -> FieldType {
+                FieldType::Atom(<Self as HdvAtom>::atom_type())
+            }
+        }
+        impl HdvSerialize for $ty {
+            fn serialize(&self, values: &mut Vec<Option<AtomValue>>) {
+                values.push(Some(self.to_atom_value()));
+            }
+            fn fill_nulls(values: &mut Vec<Option<AtomValue>>) {
+                values.push(None);
+            }
+            fn serialize_nullable(value: Option<&Self>, values: &mut Vec<Option<AtomValue>>) {
+                serialize_nullable_atom(value, values);
+            }
+            fn fill_nullable_nulls(values: &mut Vec<Option<AtomValue>>) {
+                fill_nullable_atom_nulls::<Self>(values);
+            }
+        }
+        impl HdvDeserialize for $ty {
+            fn deserialize(values: &mut &[Option<AtomValue>]) -> Option<Self> {
+                let value = values.first()?;
+                *values = &values[1..];
+                Self::from_atom_value(value.as_ref()?)
+            }
+            fn deserialize_nullable(values: &mut &[Option<AtomValue>]) -> Option<Option<Self>> {
+                deserialize_nullable_atom(values)
+            }
+        }
+        impl HdvDeserializeBorrowed for $ty {
+            type Borrowed<'a> = Self;
+            fn deserialize_borrowed<'a>(values: &mut &[Option<AtomValueRef<'a>>]) -> Option<Self> {
+                let value = values.first()?;
+                *values = &values[1..];
+                Self::from_atom_value(&value.as_ref()?.to_owned())
+            }
+            fn deserialize_borrowed_nullable<'a>(
+                values: &mut &[Option<AtomValueRef<'a>>],
+            ) -> Option<Option<Self::Borrowed<'a>>> {
+                deserialize_borrowed_nullable_atom::<Self>(values)
+            }
+        }
+    };
+}
+/// A standalone atom or list is an object of a single column named `0`
+fn single_column_scheme(ty: FieldType) -> ObjectScheme {
+    ObjectScheme::new(vec![FieldScheme {
+        name: "0".to_string(),
+        ty,
+        nullable: false,
+        default: None,
+        aliases: vec![],
+        flatten: false,
+    }])
+}
+/// Integers are stored in [`AtomValue::U64`] or [`AtomValue::I64`] and typed by their width
+macro_rules! impl_hdv_atom_int {
+    ($arm:ident, $get:ident, $($ty:ty => $atom_type:ident),*) => {
+        $(
+            impl HdvAtom for $ty {
+                fn atom_type() -> AtomType {
+                    AtomType::$atom_type
+                }
+                fn to_atom_value(&self) -> AtomValue {
+                    AtomValue::$arm((*self).try_into().unwrap())
+                }
+                fn from_atom_value(value: &AtomValue) -> Option<Self> {
+                    value.$get()?.try_into().ok()
+                }
+            }
+            impl_hdv_atom_traits!($ty);
+        )*
+    };
+}
+impl_hdv_atom_int!(U64, u64, u8 => U8, u16 => U16, u32 => U32, u64 => U64, usize => U64);
+impl_hdv_atom_int!(I64, i64, i8 => I8, i16 => I16, i32 => I32, i64 => I64, isize => I64);
+impl_hdv_atom!(f32, F32, f32, |x| *x, |v| Some(v));
+impl_hdv_atom!(f64, F64, f64, |x| *x, |v| Some(v));
+impl_hdv_atom!(bool, Bool, bool, |x| *x, |v| Some(v));
+impl_hdv_atom!(u128, U128, u128, |x| *x, |v| Some(v));
+impl_hdv_atom!(i128, I128, i128, |x| *x, |v| Some(v));
+#[cfg(feature = "uuid")]
+impl_hdv_atom!(uuid::Uuid, Uuid, uuid, |x| *x.as_bytes(), |v| Some(
+    uuid::Uuid::from_bytes(v)
+));
+/// Decimal places of [`rust_decimal::Decimal`] columns
+///
+/// All decimal places of the type are kept, leaving 10 integer digits to the precision of 38.
+#[cfg(feature = "rust_decimal")]
+const RUST_DECIMAL_SCALE: u32 = 28;
+/// Values of more than 10 integer digits do not fit the column and are rejected by writers.
+#[cfg(feature = "rust_decimal")]
+impl HdvAtom for rust_decimal::Decimal {
+    fn atom_type() -> AtomType {
+        AtomType::Decimal {
+            precision: 38,
+            scale: RUST_DECIMAL_SCALE as u8,
+        }
+    }
+    fn to_atom_value(&self) -> AtomValue {
+        let factor = 10_i128.pow(RUST_DECIMAL_SCALE - self.scale());
+        AtomValue::Decimal(self.mantissa().saturating_mul(factor))
+    }
+    fn from_atom_value(value: &AtomValue) -> Option<Self> {
+        // Trailing zeros might not fit in the 96-bit mantissa
+        let (mut mantissa, mut scale) = (value.decimal()?, RUST_DECIMAL_SCALE);
+        while 0 < scale && mantissa % 10 == 0 {
+            mantissa /= 10;
+            scale -= 1;
+        }
+        rust_decimal::Decimal::try_from_i128_with_scale(mantissa, scale).ok()
+    }
+}
+#[cfg(feature = "rust_decimal")]
+impl_hdv_atom_traits!(rust_decimal::Decimal);
+impl_hdv_atom!(Arc<str>, String, string, |x| x.clone(), |v| Some(v.clone()));
+impl_hdv_atom!(Arc<[u8]>, Bytes, bytes, |x| x.clone(), |v| Some(v.clone()));
+macro_rules! impl_hdv_atom_owned {
+    ($arm:ident, $get:ident, $($ty:ty),*) => {
+        $(
+            impl_hdv_atom!($ty, $arm, $get, |x| (&**x).into(), |v| Some((&**v).into()));
+        )*
+    };
+}
+impl_hdv_atom_owned!(String, string, String, Box<str>, Rc<str>);
+impl_hdv_atom_owned!(Bytes, bytes, Vec<u8>, Box<[u8]>, Rc<[u8]>);
+
+macro_rules! impl_hdv_atom_borrowed {
+    ($ty:ty, $arm:ident, $get:ident) => {
+        impl HdvScheme for &$ty {
+            fn object_scheme() -> ObjectScheme {
+                <<$ty as ToOwned>::Owned as HdvScheme>::object_scheme()
+            }
+            fn field_type() -> FieldType {
+                FieldType::Atom(AtomType::$arm)
+            }
+        }
+        impl HdvSerialize for &$ty {
+            fn serialize(&self, values: &mut Vec<Option<AtomValue>>) {
+                values.push(Some(AtomValue::$arm((*self).into())));
+            }
+            fn fill_nulls(values: &mut Vec<Option<AtomValue>>) {
+                values.push(None);
+            }
+            fn serialize_nullable(value: Option<&Self>, values: &mut Vec<Option<AtomValue>>) {
+                serialize_nullable_atom(value, values);
+            }
+            fn fill_nullable_nulls(values: &mut Vec<Option<AtomValue>>) {
+                fill_nullable_atom_nulls::<Self>(values);
+            }
+        }
+        impl HdvDeserializeBorrowed for &$ty {
+            type Borrowed<'a> = &'a $ty;
+            fn deserialize_borrowed<'a>(
+                values: &mut &[Option<AtomValueRef<'a>>],
+            ) -> Option<&'a $ty> {
+                let value = values.first()?;
+                *values = &values[1..];
+                value.as_ref()?.$get()
+            }
+            fn deserialize_borrowed_nullable<'a>(
+                values: &mut &[Option<AtomValueRef<'a>>],
+            ) -> Option<Option<Self::Borrowed<'a>>> {
+                deserialize_borrowed_nullable_atom::<Self>(values)
+            }
+        }
+    };
+}
+impl_hdv_atom_borrowed!(str, String, string);
+impl_hdv_atom_borrowed!([u8], Bytes, bytes);
+
+/// Atom stored as an item of a list column or a key or value of a map column
+///
+/// `u8` is not an item since `Vec<u8>` is stored as bytes.
+pub trait HdvItem: HdvAtom {
+    fn item_type() -> ItemType;
+}
+macro_rules! impl_hdv_item {
+    ($arm:ident, $($ty:ty),*) => {
+        $(
+            impl HdvItem for $ty {
+                fn item_type() -> ItemType {
+                    ItemType::$arm
+                }
+            }
+        )*
+    };
+}
+impl_hdv_item!(U64, u16, u32, u64, usize);
+impl_hdv_item!(I64, i8, i16, i32, i64, isize);
+impl_hdv_item!(F32, f32);
+impl_hdv_item!(F64, f64);
+impl_hdv_item!(Bool, bool);
+impl_hdv_item!(String, Arc<str>, String, Box<str>, Rc<str>);
+impl_hdv_item!(Bytes, Arc<[u8]>, Vec<u8>, Box<[u8]>, Rc<[u8]>);
+
+impl<T: HdvItem> HdvScheme for Vec<T> {
+    fn object_scheme() -> ObjectScheme {
+        single_column_scheme(Self::field_type())
+    }
+    fn field_type() -> FieldType {
+        FieldType::Atom(AtomType::List(T::item_type()))
+    }
+}
+impl<T: HdvItem> HdvSerialize for Vec<T> {
+    fn serialize(&self, values: &mut Vec<Option<AtomValue>>) {
+        let items = self.iter().map(|x| x.to_atom_value()).collect();
+        values.push(Some(AtomValue::List(T::item_type(), items)));
+    }
+    fn fill_nulls(values: &mut Vec<Option<AtomValue>>) {
+        values.push(None);
+    }
+    fn serialize_nullable(value: Option<&Self>, values: &mut Vec<Option<AtomValue>>) {
+        serialize_nullable_atom(value, values);
+    }
+    fn fill_nullable_nulls(values: &mut Vec<Option<AtomValue>>) {
+        fill_nullable_atom_nulls::<Self>(values);
+    }
+}
+impl<T: HdvItem> HdvDeserialize for Vec<T> {
+    fn deserialize(values: &mut &[Option<AtomValue>]) -> Option<Self> {
+        let value = values.first()?;
+        *values = &values[1..];
+        value
+            .as_ref()?
+            .list()?
+            .iter()
+            .map(T::from_atom_value)
+            .collect()
+    }
+    fn deserialize_nullable(values: &mut &[Option<AtomValue>]) -> Option<Option<Self>> {
+        deserialize_nullable_atom(values)
+    }
+}
+impl<T: HdvItem> HdvDeserializeBorrowed for Vec<T> {
+    type Borrowed<'a> = Self;
+    fn deserialize_borrowed<'a>(values: &mut &[Option<AtomValueRef<'a>>]) -> Option<Self> {
+        let value = values.first()?;
+        *values = &values[1..];
+        value
+            .as_ref()?
+            .list()?
+            .iter()
+            .map(|x| T::from_atom_value(&x.to_owned()))
+            .collect()
+    }
+    fn deserialize_borrowed_nullable<'a>(
+        values: &mut &[Option<AtomValueRef<'a>>],
+    ) -> Option<Option<Self::Borrowed<'a>>> {
+        deserialize_borrowed_nullable_atom::<Self>(values)
+    }
+}
+
+macro_rules! impl_hdv_map {
+    ($map:ident, $($bound:path),*) => {
+        impl<K: HdvItem $(+ $bound)*, V: HdvItem> HdvScheme for $map<K, V> {
+            fn object_scheme() -> ObjectScheme {
+                single_column_scheme(Self::field_type())
+            }
+            fn field_type() -> FieldType {
+                FieldType::Atom(AtomType::Map(K::item_type(), V::item_type()))
+            }
+        }
+        impl<K: HdvItem $(+ $bound)*, V: HdvItem> HdvSerialize for $map<K, V> {
+            fn serialize(&self, values: &mut Vec<Option<AtomValue>>) {
+                let entries = self
+                    .iter()
+                    .map(|(k, v)| (k.to_atom_value(), v.to_atom_value()))
+                    .collect();
+                values.push(Some(AtomValue::Map(K::item_type(), V::item_type(), entries)));
+            }
+            fn fill_nulls(values: &mut Vec<Option<AtomValue>>) {
+                values.push(None);
+            }
+            fn serialize_nullable(value: Option<&Self>, values: &mut Vec<Option<AtomValue>>) {
+                serialize_nullable_atom(value, values);
+            }
+            fn fill_nullable_nulls(values: &mut Vec<Option<AtomValue>>) {
+                fill_nullable_atom_nulls::<Self>(values);
+            }
+        }
+        impl<K: HdvItem $(+ $bound)*, V: HdvItem> HdvDeserialize for $map<K, V> {
+            fn deserialize(values: &mut &[Option<AtomValue>]) -> Option<Self> {
+                let value = values.first()?;
+                *values = &values[1..];
+                value
+                    .as_ref()?
+                    .map()?
+                    .iter()
+                    .map(|(k, v)| Some((K::from_atom_value(k)?, V::from_atom_value(v)?)))
+                    .collect()
+            }
+            fn deserialize_nullable(values: &mut &[Option<AtomValue>]) -> Option<Option<Self>> {
+                deserialize_nullable_atom(values)
+            }
+        }
+        impl<K: HdvItem $(+ $bound)*, V: HdvItem> HdvDeserializeBorrowed for $map<K, V> {
+            type Borrowed<'a> = Self;
+            fn deserialize_borrowed<'a>(values: &mut &[Option<AtomValueRef<'a>>]) -> Option<Self> {
+                let value = values.first()?;
+                *values = &values[1..];
+                value
+                    .as_ref()?
+                    .map()?
+                    .iter()
+                    .map(|(k, v)| {
+                        let k = K::from_atom_value(&k.to_owned())?;
+                        let v = V::from_atom_value(&v.to_owned())?;
+                        Some((k, v))
+                    })
+                    .collect()
+            }
+            fn deserialize_borrowed_nullable<'a>(
+                values: &mut &[Option<AtomValueRef<'a>>],
+            ) -> Option<Option<Self::Borrowed<'a>>> {
+                deserialize_borrowed_nullable_atom::<Self>(values)
+            }
+        }
+    };
+}
+impl_hdv_map!(HashMap, Eq, Hash);
+impl_hdv_map!(BTreeMap, Ord);
+
+fn microseconds_utc() -> AtomType {
+    AtomType::Timestamp {
+        unit: TimeUnit::Microseconds,
+        tz: Some("UTC".into()),
+    }
+}
+#[cfg(any(feature = "chrono", feature = "time"))]
+fn microseconds_naive() -> AtomType {
+    AtomType::Timestamp {
+        unit: TimeUnit::Microseconds,
+        tz: None,
+    }
+}
+fn microseconds_duration() -> AtomType {
+    AtomType::Duration {
+        unit: TimeUnit::Microseconds,
+    }
+}
+/// Microseconds beyond the range of `i64` saturate.
+fn saturating_micros(micros: i128) -> i64 {
+    micros.clamp(i64::MIN.into(), i64::MAX.into()) as i64
+}
+
+/// Truncated to microseconds
+///
+/// Times more than 292,000 years away from the Unix epoch saturate.
+impl HdvAtom for SystemTime {
+    fn atom_type() -> AtomType {
+        microseconds_utc()
+    }
+    fn to_atom_value(&self) -> AtomValue {
+        let micros = match self.duration_since(UNIX_EPOCH) {
+            Ok(x) => i128::try_from(x.as_micros()).unwrap(),
+            Err(e) => -i128::try_from(e.duration().as_micros()).unwrap(),
+        };
+        AtomValue::Timestamp(saturating_micros(micros))
+    }
+    fn from_atom_value(value: &AtomValue) -> Option<Self> {
+        let micros = value.timestamp()?;
+        let duration = Duration::from_micros(micros.unsigned_abs());
+        if micros < 0 {
+            UNIX_EPOCH.checked_sub(duration)
+        } else {
+            UNIX_EPOCH.checked_add(duration)
+        }
+    }
+}
+impl_hdv_atom_traits!(SystemTime);
+/// Truncated to microseconds
+///
+/// Durations longer than 292,000 years saturate.
+impl HdvAtom for Duration {
+    fn atom_type() -> AtomType {
+        microseconds_duration()
+    }
+    fn to_atom_value(&self) -> AtomValue {
+        let micros = i128::try_from(self.as_micros()).unwrap();
+        AtomValue::Duration(saturating_micros(micros))
+    }
+    fn from_atom_value(value: &AtomValue) -> Option<Self> {
+        let micros = value.duration()?;
+        Some(Duration::from_micros(micros.try_into().ok()?))
+    }
+}
+impl_hdv_atom_traits!(Duration);
+
+/// Days from 0001-01-01 to 1970-01-01
+#[cfg(feature = "chrono")]
+const CHRONO_EPOCH_DAYS_FROM_CE: i32 = 719163;
+/// Truncated to microseconds
+#[cfg(feature = "chrono")]
+impl HdvAtom for chrono::DateTime<chrono::Utc> {
+    fn atom_type() -> AtomType {
+        microseconds_utc()
+    }
+    fn to_atom_value(&self) -> AtomValue {
+        AtomValue::Timestamp(self.timestamp_micros())
+    }
+    fn from_atom_value(value: &AtomValue) -> Option<Self> {
+        chrono::DateTime::from_timestamp_micros(value.timestamp()?)
+    }
+}
+#[cfg(feature = "chrono")]
+impl_hdv_atom_traits!(chrono::DateTime<chrono::Utc>);
+/// Truncated to microseconds
+#[cfg(feature = "chrono")]
+impl HdvAtom for chrono::NaiveDateTime {
+    fn atom_type() -> AtomType {
+        microseconds_naive()
+    }
+    fn to_atom_value(&self) -> AtomValue {
+        AtomValue::Timestamp(self.and_utc().timestamp_micros())
+    }
+    fn from_atom_value(value: &AtomValue) -> Option<Self> {
+        Some(chrono::DateTime::from_timestamp_micros(value.timestamp()?)?.naive_utc())
+    }
+}
+#[cfg(feature = "chrono")]
+impl_hdv_atom_traits!(chrono::NaiveDateTime);
+#[cfg(feature = "chrono")]
+impl HdvAtom for chrono::NaiveDate {
+    fn atom_type() -> AtomType {
+        AtomType::Date
+    }
+    fn to_atom_value(&self) -> AtomValue {
+        use chrono::Datelike;
+        AtomValue::Date(self.num_days_from_ce() - CHRONO_EPOCH_DAYS_FROM_CE)
+    }
+    fn from_atom_value(value: &AtomValue) -> Option<Self> {
+        let days = value.date()?.checked_add(CHRONO_EPOCH_DAYS_FROM_CE)?;
+        chrono::NaiveDate::from_num_days_from_ce_opt(days)
+    }
+}
+#[cfg(feature = "chrono")]
+impl_hdv_atom_traits!(chrono::NaiveDate);
+/// Truncated to microseconds
+///
+/// Durations longer than 292,000 years saturate.
+#[cfg(feature = "chrono")]
+impl HdvAtom for chrono::TimeDelta {
+    fn atom_type() -> AtomType {
+        microseconds_duration()
+    }
+    fn to_atom_value(&self) -> AtomValue {
+        let micros = self
+            .num_microseconds()
+            .unwrap_or(match *self < chrono::TimeDelta::zero() {
+                true => i64::MIN,
+                false => i64::MAX,
+            });
+        AtomValue::Duration(micros)
+    }
+    fn from_atom_value(value: &AtomValue) -> Option<Self> {
+        Some(chrono::TimeDelta::microseconds(value.duration()?))
+    }
+}
+#[cfg(feature = "chrono")]
+impl_hdv_atom_traits!(chrono::TimeDelta);
+
+/// Julian day of 1970-01-01
+#[cfg(feature = "time")]
+const TIME_EPOCH_JULIAN_DAY: i32 = 2440588;
+/// Stored in UTC and truncated to microseconds
+#[cfg(feature = "time")]
+impl HdvAtom for time::OffsetDateTime {
+    fn atom_type() -> AtomType {
+        microseconds_utc()
+    }
+    fn to_atom_value(&self) -> AtomValue {
+        AtomValue::Timestamp(saturating_micros(self.unix_timestamp_nanos() / 1_000))
+    }
+    fn from_atom_value(value: &AtomValue) -> Option<Self> {
+        let nanos = i128::from(value.timestamp()?) * 1_000;
+        time::OffsetDateTime::from_unix_timestamp_nanos(nanos).ok()
+    }
+}
+#[cfg(feature = "time")]
+impl_hdv_atom_traits!(time::OffsetDateTime);
+/// Truncated to microseconds
+#[cfg(feature = "time")]
+impl HdvAtom for time::PrimitiveDateTime {
+    fn atom_type() -> AtomType {
+        microseconds_naive()
+    }
+    fn to_atom_value(&self) -> AtomValue {
+        let nanos = self.assume_utc().unix_timestamp_nanos();
+        AtomValue::Timestamp(saturating_micros(nanos / 1_000))
+    }
+    fn from_atom_value(value: &AtomValue) -> Option<Self> {
+        let nanos = i128::from(value.timestamp()?) * 1_000;
+        let time = time::OffsetDateTime::from_unix_timestamp_nanos(nanos).ok()?;
+        Some(time::PrimitiveDateTime::new(time.date(), time.time()))
+    }
+}
+#[cfg(feature = "time")]
+impl_hdv_atom_traits!(time::PrimitiveDateTime);
+#[cfg(feature = "time")]
+impl HdvAtom for time::Date {
+    fn atom_type() -> AtomType {
+        AtomType::Date
+    }
+    fn to_atom_value(&self) -> AtomValue {
+        AtomValue::Date(self.to_julian_day() - TIME_EPOCH_JULIAN_DAY)
+    }
+    fn from_atom_value(value: &AtomValue) -> Option<Self> {
+        let day = value.date()?.checked_add(TIME_EPOCH_JULIAN_DAY)?;
+        time::Date::from_julian_day(day).ok()
+    }
+}
+#[cfg(feature = "time")]
+impl_hdv_atom_traits!(time::Date);
+/// Truncated to microseconds
+///
+/// Durations longer than 292,000 years saturate.
+#[cfg(feature = "time")]
+impl HdvAtom for time::Duration {
+    fn atom_type() -> AtomType {
+        microseconds_duration()
+    }
+    fn to_atom_value(&self) -> AtomValue {
+        AtomValue::Duration(saturating_micros(self.whole_microseconds()))
+    }
+    fn from_atom_value(value: &AtomValue) -> Option<Self> {
+        Some(time::Duration::microseconds(value.duration()?))
+    }
+}
+#[cfg(feature = "time")]
+impl_hdv_atom_traits!(time::Duration);
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -93,47 +1041,65 @@ mod tests {
         }
         impl HdvScheme for A {
             fn object_scheme() -> ObjectScheme {
-                ObjectScheme {
-                    fields: vec![
-                        FieldScheme {
-                            name: "a".to_string(),
-                            ty: FieldType::Atom(AtomType::U64),
-                        },
-                        FieldScheme {
-                            name: "b".to_string(),
-                            ty: FieldType::Object(<B as HdvScheme>::object_scheme()),
-                        },
-                        FieldScheme {
-                            name: "c".to_string(),
-                            ty: FieldType::Atom(AtomType::F64),
-                        },
-                        FieldScheme {
-                            name: "d".to_string(),
-                            ty: FieldType::Object(<B as HdvScheme>::object_scheme()),
-                        },
-                    ],
-                }
+                ObjectScheme::new(vec![
+                    FieldScheme {
+                        name: "a".to_string(),
+                        ty: FieldType::Atom(AtomType::U64),
+                        nullable: false,
+                        default: None,
+                        aliases: vec![],
+                        flatten: false,
+                    },
+                    FieldScheme {
+                        name: "b".to_string(),
+                        ty: FieldType::Object(<B as HdvScheme>::object_scheme()),
+                        nullable: true,
+                        default: None,
+                        aliases: vec![],
+                        flatten: false,
+                    },
+                    FieldScheme {
+                        name: "c".to_string(),
+                        ty: FieldType::Atom(AtomType::F64),
+                        nullable: true,
+                        default: None,
+                        aliases: vec![],
+                        flatten: false,
+                    },
+                    FieldScheme {
+                        name: "d".to_string(),
+                        ty: FieldType::Object(<B as HdvScheme>::object_scheme()),
+                        nullable: false,
+                        default: None,
+                        aliases: vec![],
+                        flatten: false,
+                    },
+                ])
             }
         }
         impl HdvSerialize for A {
             #[allow(clippy::redundant_closure)]
             fn serialize(&self, values: &mut Vec<Option<AtomValue>>) {
                 values.push(Some(AtomValue::U64(self.a as _)));
-                if let Some(x) = self.b.as_ref() {
-                    HdvSerialize::serialize(x, values);
-                } else {
-                    <B as HdvSerialize>::fill_nulls(values);
-                }
+                <B as HdvSerialize>::serialize_nullable(self.b.as_ref(), values);
                 values.push(self.c.map(|x| AtomValue::F64(x as _)));
                 HdvSerialize::serialize(&self.d, values);
             }
 
             fn fill_nulls(values: &mut Vec<Option<AtomValue>>) {
                 values.push(None);
-                <B as HdvSerialize>::fill_nulls(values);
+                <B as HdvSerialize>::fill_nullable_nulls(values);
                 values.push(None);
                 <B as HdvSerialize>::fill_nulls(values);
             }
+
+            fn serialize_nullable(value: Option<&Self>, values: &mut Vec<Option<AtomValue>>) {
+                serialize_nullable_object(value, values);
+            }
+
+            fn fill_nullable_nulls(values: &mut Vec<Option<AtomValue>>) {
+                fill_nullable_object_nulls::<Self>(values);
+            }
         }
         impl HdvDeserialize for A {
             #[allow(clippy::redundant_field_names)]
@@ -143,7 +1109,7 @@ mod tests {
                     *__values = &__values[1..];
                     value
                 };
-                let b = <B as HdvDeserialize>::deserialize(__values);
+                let b = <B as HdvDeserialize>::deserialize_nullable(__values);
                 let c = {
                     let value = __values.first()?.as_ref();
                     *__values = &__values[1..];
@@ -152,11 +1118,15 @@ mod tests {
                 let d = <B as HdvDeserialize>::deserialize(__values);
                 Some(Self {
                     a: a?.u64().unwrap() as _,
-                    b: b,
+                    b: b?,
                     c: c.map(|x| x.f64().unwrap() as _),
                     d: d?,
                 })
             }
+
+            fn deserialize_nullable(values: &mut &[Option<AtomValue>]) -> Option<Option<Self>> {
+                deserialize_nullable_object(values)
+            }
         }
 
         #[derive(Debug, PartialEq)]
@@ -168,26 +1138,40 @@ mod tests {
         }
         impl HdvScheme for B {
             fn object_scheme() -> ObjectScheme {
-                ObjectScheme {
-                    fields: vec![
-                        FieldScheme {
-                            name: "a".to_string(),
-                            ty: FieldType::Atom(AtomType::Bytes),
-                        },
-                        FieldScheme {
-                            name: "b".to_string(),
-                            ty: FieldType::Atom(AtomType::I64),
-                        },
-                        FieldScheme {
-                            name: "c".to_string(),
-                            ty: FieldType::Atom(AtomType::String),
-                        },
-                        FieldScheme {
-                            name: "d".to_string(),
-                            ty: FieldType::Atom(AtomType::Bytes),
-                        },
-                    ],
-                }
+                ObjectScheme::new(vec![
+                    FieldScheme {
+                        name: "a".to_string(),
+                        ty: FieldType::Atom(AtomType::Bytes),
+                        nullable: false,
+                        default: None,
+                        aliases: vec![],
+                        flatten: false,
+                    },
+                    FieldScheme {
+                        name: "b".to_string(),
+                        ty: FieldType::Atom(AtomType::I64),
+                        nullable: false,
+                        default: None,
+                        aliases: vec![],
+                        flatten: false,
+                    },
+                    FieldScheme {
+                        name: "c".to_string(),
+                        ty: FieldType::Atom(AtomType::String),
+                        nullable: false,
+                        default: None,
+                        aliases: vec![],
+                        flatten: false,
+                    },
+                    FieldScheme {
+                        name: "d".to_string(),
+                        ty: FieldType::Atom(AtomType::Bytes),
+                        nullable: true,
+                        default: None,
+                        aliases: vec![],
+                        flatten: false,
+                    },
+                ])
             }
         }
         impl HdvSerialize for B {
@@ -204,6 +1188,14 @@ mod tests {
                 values.push(None);
                 values.push(None);
             }
+
+            fn serialize_nullable(value: Option<&Self>, values: &mut Vec<Option<AtomValue>>) {
+                serialize_nullable_object(value, values);
+            }
+
+            fn fill_nullable_nulls(values: &mut Vec<Option<AtomValue>>) {
+                fill_nullable_object_nulls::<Self>(values);
+            }
         }
         impl HdvDeserialize for B {
             fn deserialize(values: &mut &[Option<AtomValue>]) -> Option<Self> {
@@ -234,6 +1226,10 @@ mod tests {
                     d: d.map(|x| x.bytes().unwrap().to_owned()),
                 })
             }
+
+            fn deserialize_nullable(values: &mut &[Option<AtomValue>]) -> Option<Option<Self>> {
+                deserialize_nullable_object(values)
+            }
         }
 
         let a = A {
@@ -251,64 +1247,106 @@ mod tests {
         let scheme = A::object_scheme();
         assert_eq!(
             scheme,
-            ObjectScheme {
-                fields: vec![
-                    FieldScheme {
-                        name: "a".to_owned(),
-                        ty: FieldType::Atom(AtomType::U64,),
-                    },
-                    FieldScheme {
-                        name: "b".to_owned(),
-                        ty: FieldType::Object(ObjectScheme {
-                            fields: vec![
-                                FieldScheme {
-                                    name: "a".to_owned(),
-                                    ty: FieldType::Atom(AtomType::Bytes,),
-                                },
-                                FieldScheme {
-                                    name: "b".to_owned(),
-                                    ty: FieldType::Atom(AtomType::I64,),
-                                },
-                                FieldScheme {
-                                    name: "c".to_owned(),
-                                    ty: FieldType::Atom(AtomType::String,),
-                                },
-                                FieldScheme {
-                                    name: "d".to_owned(),
-                                    ty: FieldType::Atom(AtomType::Bytes,),
-                                },
-                            ]
-                        }),
-                    },
-                    FieldScheme {
-                        name: "c".to_owned(),
-                        ty: FieldType::Atom(AtomType::F64,),
-                    },
-                    FieldScheme {
-                        name: "d".to_owned(),
-                        ty: FieldType::Object(ObjectScheme {
-                            fields: vec![
-                                FieldScheme {
-                                    name: "a".to_owned(),
-                                    ty: FieldType::Atom(AtomType::Bytes,),
-                                },
-                                FieldScheme {
-                                    name: "b".to_owned(),
-                                    ty: FieldType::Atom(AtomType::I64,),
-                                },
-                                FieldScheme {
-                                    name: "c".to_owned(),
-                                    ty: FieldType::Atom(AtomType::String,),
-                                },
-                                FieldScheme {
-                                    name: "d".to_owned(),
-                                    ty: FieldType::Atom(AtomType::Bytes,),
-                                },
-                            ]
-                        }),
-                    },
-                ]
-            }
+            ObjectScheme::new(vec![
+                FieldScheme {
+                    name: "a".to_owned(),
+                    ty: FieldType::Atom(AtomType::U64,),
+                    nullable: false,
+                    default: None,
+                    aliases: vec![],
+                    flatten: false,
+                },
+                FieldScheme {
+                    name: "b".to_owned(),
+                    ty: FieldType::Object(ObjectScheme::new(vec![
+                        FieldScheme {
+                            name: "a".to_owned(),
+                            ty: FieldType::Atom(AtomType::Bytes,),
+                            nullable: false,
+                            default: None,
+                            aliases: vec![],
+                            flatten: false,
+                        },
+                        FieldScheme {
+                            name: "b".to_owned(),
+                            ty: FieldType::Atom(AtomType::I64,),
+                            nullable: false,
+                            default: None,
+                            aliases: vec![],
+                            flatten: false,
+                        },
+                        FieldScheme {
+                            name: "c".to_owned(),
+                            ty: FieldType::Atom(AtomType::String,),
+                            nullable: false,
+                            default: None,
+                            aliases: vec![],
+                            flatten: false,
+                        },
+                        FieldScheme {
+                            name: "d".to_owned(),
+                            ty: FieldType::Atom(AtomType::Bytes,),
+                            nullable: true,
+                            default: None,
+                            aliases: vec![],
+                            flatten: false,
+                        },
+                    ])),
+                    nullable: true,
+                    default: None,
+                    aliases: vec![],
+                    flatten: false,
+                },
+                FieldScheme {
+                    name: "c".to_owned(),
+                    ty: FieldType::Atom(AtomType::F64,),
+                    nullable: true,
+                    default: None,
+                    aliases: vec![],
+                    flatten: false,
+                },
+                FieldScheme {
+                    name: "d".to_owned(),
+                    ty: FieldType::Object(ObjectScheme::new(vec![
+                        FieldScheme {
+                            name: "a".to_owned(),
+                            ty: FieldType::Atom(AtomType::Bytes,),
+                            nullable: false,
+                            default: None,
+                            aliases: vec![],
+                            flatten: false,
+                        },
+                        FieldScheme {
+                            name: "b".to_owned(),
+                            ty: FieldType::Atom(AtomType::I64,),
+                            nullable: false,
+                            default: None,
+                            aliases: vec![],
+                            flatten: false,
+                        },
+                        FieldScheme {
+                            name: "c".to_owned(),
+                            ty: FieldType::Atom(AtomType::String,),
+                            nullable: false,
+                            default: None,
+                            aliases: vec![],
+                            flatten: false,
+                        },
+                        FieldScheme {
+                            name: "d".to_owned(),
+                            ty: FieldType::Atom(AtomType::Bytes,),
+                            nullable: true,
+                            default: None,
+                            aliases: vec![],
+                            flatten: false,
+                        },
+                    ])),
+                    nullable: false,
+                    default: None,
+                    aliases: vec![],
+                    flatten: false,
+                },
+            ])
         );
 
         assert_eq!(
@@ -318,6 +1356,10 @@ mod tests {
                     name: "a".to_owned(),
                     r#type: AtomType::U64,
                 },
+                AtomScheme {
+                    name: "b".to_owned(),
+                    r#type: AtomType::Bool,
+                },
                 AtomScheme {
                     name: "b.a".to_owned(),
                     r#type: AtomType::Bytes,
@@ -363,6 +1405,7 @@ mod tests {
             values,
             [
                 Some(AtomValue::U64(1)),
+                Some(AtomValue::Bool(false)),
                 None,
                 None,
                 None,
@@ -378,4 +1421,144 @@ mod tests {
         let b = A::deserialize(&mut &*values).unwrap();
         assert_eq!(a, b);
     }
+
+    #[test]
+    fn test_from_atom_schemes() {
+        let header = |names: &[(&str, AtomType)]| {
+            names
+                .iter()
+                .map(|(name, r#type)| AtomScheme {
+                    name: name.to_string(),
+                    r#type: r#type.clone(),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let atoms = header(&[
+            ("a", AtomType::U64),
+            ("b", AtomType::Bool),
+            ("b.a", AtomType::String),
+            ("b.b.a", AtomType::F64),
+            ("c", AtomType::Bool),
+        ]);
+        let object = ObjectScheme::from_atom_schemes(&atoms).unwrap();
+        assert_eq!(object.atom_schemes(), atoms);
+        assert_eq!(object.fields[1].atom_schemes(), atoms[1..4]);
+        let field = |name: &str, ty: FieldType, nullable: bool| FieldScheme {
+            name: name.to_string(),
+            ty,
+            nullable,
+            default: None,
+            aliases: vec![],
+            flatten: false,
+        };
+        assert_eq!(
+            object,
+            ObjectScheme::new(vec![
+                field("a", FieldType::Atom(AtomType::U64), true),
+                field(
+                    "b",
+                    FieldType::Object(ObjectScheme::new(vec![
+                        field("a", FieldType::Atom(AtomType::String), true),
+                        field(
+                            "b",
+                            FieldType::Object(ObjectScheme::new(vec![field(
+                                "a",
+                                FieldType::Atom(AtomType::F64),
+                                true
+                            )])),
+                            false
+                        ),
+                    ])),
+                    true
+                ),
+                field("c", FieldType::Atom(AtomType::Bool), true),
+            ])
+        );
+
+        for names in [
+            &[("a", AtomType::U64), ("a.b", AtomType::U64)][..],
+            &[("a.b", AtomType::U64), ("a", AtomType::Bool)],
+            &[("a", AtomType::U64), ("a", AtomType::U64)],
+            &[
+                ("a.b", AtomType::U64),
+                ("c", AtomType::U64),
+                ("a.d", AtomType::U64),
+            ],
+            &[("a..b", AtomType::U64)],
+        ] {
+            let err = ObjectScheme::from_atom_schemes(&header(names)).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[cfg(all(feature = "chrono", feature = "time"))]
+    #[test]
+    fn test_chrono_time() {
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        let time = time::Date::from_calendar_date(2024, time::Month::February, 29).unwrap();
+        assert_eq!(date.to_atom_value(), time.to_atom_value());
+        assert_eq!(date.to_atom_value(), AtomValue::Date(19782));
+        assert_eq!(
+            time::Date::from_atom_value(&date.to_atom_value()),
+            Some(time)
+        );
+
+        let date_time = date.and_hms_micro_opt(1, 2, 3, 4).unwrap();
+        let offset_date_time = time
+            .with_hms_micro(1, 2, 3, 4)
+            .unwrap()
+            .assume_offset(time::UtcOffset::from_hms(1, 0, 0).unwrap());
+        let value = date_time.and_utc().to_atom_value();
+        assert_eq!(
+            chrono::NaiveDateTime::from_atom_value(&value),
+            Some(date_time)
+        );
+        assert_eq!(
+            offset_date_time.to_atom_value(),
+            AtomValue::Timestamp(value.timestamp().unwrap() - 3_600_000_000)
+        );
+
+        let delta = chrono::TimeDelta::milliseconds(-1_500);
+        let duration = time::Duration::milliseconds(-1_500);
+        assert_eq!(delta.to_atom_value(), duration.to_atom_value());
+        assert_eq!(
+            chrono::TimeDelta::from_atom_value(&delta.to_atom_value()),
+            Some(delta)
+        );
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn test_uuid() {
+        let id = uuid::Uuid::from_u128(0x67e55044_10b1_426f_9247_bb680e5fe0c8);
+        let value = id.to_atom_value();
+        assert_eq!(value, AtomValue::Uuid(*id.as_bytes()));
+        assert_eq!(uuid::Uuid::from_atom_value(&value), Some(id));
+        assert_eq!(<uuid::Uuid as HdvAtom>::atom_type(), AtomType::Uuid);
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn test_rust_decimal() {
+        let x = rust_decimal::Decimal::new(-12345, 2);
+        let value = x.to_atom_value();
+        assert_eq!(value, AtomValue::Decimal(-12345 * 10_i128.pow(26)));
+        assert_eq!(rust_decimal::Decimal::from_atom_value(&value), Some(x));
+
+        let x = rust_decimal::Decimal::new(1, 10);
+        let value = x.to_atom_value();
+        assert_eq!(value, AtomValue::Decimal(10_i128.pow(18)));
+        assert_eq!(rust_decimal::Decimal::from_atom_value(&value), Some(x));
+
+        let atom_type = <rust_decimal::Decimal as HdvAtom>::atom_type();
+        let x = rust_decimal::Decimal::new(9_999_999_999, 0);
+        assert!(atom_type.is_type_of(&x.to_atom_value()));
+        let x = rust_decimal::Decimal::new(10_000_000_000, 0);
+        assert!(!atom_type.is_type_of(&x.to_atom_value()));
+        assert!(!atom_type.is_type_of(&rust_decimal::Decimal::MAX.to_atom_value()));
+        let mut writer = crate::io::bin::HdvBinWriter::new(vec![]);
+        let err = writer.write(&rust_decimal::Decimal::MAX).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
 }
@@ -0,0 +1,1020 @@
+//! Bridge of types implementing [`serde::Serialize`] and [`serde::Deserialize`]
+//!
+//! Structs and tuples are flattened into columns named by the paths of their fields joined with `.`.
+//! Unit variants are strings; sequences and maps of atoms are lists and maps.
+
+use std::{fmt, sync::Arc};
+
+use serde::{
+    de::{self, IntoDeserializer},
+    ser::{self, Impossible},
+    Serialize,
+};
+
+use crate::{
+    format::{AtomScheme, AtomType, AtomValue, ItemType},
+    serde::{impl_hdv_serialize_record, FieldScheme, FieldType, HdvHeader, ObjectScheme},
+};
+
+const SEPARATOR: &str = ObjectScheme::DEFAULT_SEPARATOR;
+
+/// Scheme of serde values discovered from the first value or from a sample
+#[derive(Debug)]
+pub struct SerdeBridge {
+    object_scheme: ObjectScheme,
+}
+impl SerdeBridge {
+    /// Fields that are `None` or empty in `value` have unknown types and fail the discovery.
+    pub fn from_value<T: Serialize + ?Sized>(value: &T) -> std::io::Result<Self> {
+        Self::from_sample([value])
+    }
+
+    /// A field is nullable if it is `None` or absent in any value of the sample.
+    pub fn from_sample<'a, T: Serialize + ?Sized + 'a>(
+        values: impl IntoIterator<Item = &'a T>,
+    ) -> std::io::Result<Self> {
+        let mut root = Draft::default();
+        for value in values {
+            let shape = value.serialize(ShapeSerializer).map_err(invalid_input)?;
+            let Shape::Object(_) = &shape else {
+                return Err(invalid_input(Error::not_object()));
+            };
+            root.merge(shape, "").map_err(invalid_input)?;
+        }
+        let DraftKind::Object(fields) = root.kind else {
+            return Err(invalid_input(Error("the sample is empty".to_string())));
+        };
+        let object_scheme = object_scheme(fields, "").map_err(invalid_input)?;
+        Ok(Self { object_scheme })
+    }
+
+    pub fn object_scheme(&self) -> &ObjectScheme {
+        &self.object_scheme
+    }
+
+    /// Flatten `value` into the columns of [`ObjectScheme::atom_schemes`]
+    pub fn to_values<T: Serialize + ?Sized>(
+        &self,
+        value: &T,
+    ) -> std::io::Result<Vec<Option<AtomValue>>> {
+        let shape = value.serialize(ShapeSerializer).map_err(invalid_input)?;
+        let Shape::Object(fields) = shape else {
+            return Err(invalid_input(Error::not_object()));
+        };
+        let mut values = vec![];
+        push_values(&self.object_scheme, fields, "", &mut values).map_err(invalid_input)?;
+        Ok(values)
+    }
+
+    /// [`Self::to_values`] accepted by the typed writers
+    pub fn record<T: Serialize + ?Sized>(&self, value: &T) -> std::io::Result<SerdeRecord<'_>> {
+        let values = self.to_values(value)?;
+        Ok(SerdeRecord {
+            bridge: self,
+            values,
+        })
+    }
+}
+
+/// Values of a serde value in the columns of its [`SerdeBridge`]
+#[derive(Debug)]
+pub struct SerdeRecord<'a> {
+    bridge: &'a SerdeBridge,
+    values: Vec<Option<AtomValue>>,
+}
+impl SerdeRecord<'_> {
+    pub fn values(&self) -> &[Option<AtomValue>] {
+        &self.values
+    }
+}
+impl HdvHeader for SerdeRecord<'_> {
+    fn header(&self) -> Vec<AtomScheme> {
+        self.bridge.object_scheme.atom_schemes()
+    }
+}
+impl_hdv_serialize_record!(SerdeRecord<'_>);
+
+/// Deserialize `T` from the columns of `header`
+///
+/// Fields are looked up by name, so `header` does not have to come from a [`SerdeBridge`] of `T`.
+pub fn from_values<T: de::DeserializeOwned>(
+    header: &[AtomScheme],
+    values: &[Option<AtomValue>],
+) -> std::io::Result<T> {
+    let columns = Columns { header, values };
+    let deserializer = FieldDeserializer {
+        columns,
+        name: String::new(),
+    };
+    T::deserialize(deserializer)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.0))
+}
+
+#[derive(Debug)]
+struct Error(String);
+impl Error {
+    fn not_object() -> Self {
+        Self("only structs and tuples are flattened into columns".to_string())
+    }
+    fn unsupported(name: impl fmt::Display) -> Self {
+        Self(format!("`{name}` cannot be bridged"))
+    }
+    fn mismatch(name: &str) -> Self {
+        Self(format!("field `{name}` has values of different types"))
+    }
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+impl std::error::Error for Error {}
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+fn invalid_input(e: Error) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, e.0)
+}
+
+/// Full name of `name` nested in `prefix`
+fn join(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        return name.to_string();
+    }
+    format!("{prefix}{SEPARATOR}{name}")
+}
+
+/// Serialized value before it is laid out in columns
+#[derive(Debug)]
+enum Shape {
+    Null,
+    Some(Box<Shape>),
+    Atom(AtomType, AtomValue),
+    /// List without items whose item type is unknown
+    EmptyList,
+    /// Map without entries whose key and value types are unknown
+    EmptyMap,
+    Object(Vec<(String, Shape)>),
+}
+impl Shape {
+    fn into_inner(self) -> Self {
+        match self {
+            Self::Some(x) => x.into_inner(),
+            x => x,
+        }
+    }
+}
+
+/// Scheme of a field discovered so far
+#[derive(Debug, Default)]
+struct Draft {
+    kind: DraftKind,
+    nullable: bool,
+}
+#[derive(Debug, Default)]
+enum DraftKind {
+    #[default]
+    Unknown,
+    EmptyList,
+    EmptyMap,
+    Atom(AtomType),
+    Object(Vec<(String, Draft)>),
+}
+impl Draft {
+    fn merge(&mut self, shape: Shape, name: &str) -> Result<(), Error> {
+        let kind = &mut self.kind;
+        match shape {
+            Shape::Null => self.nullable = true,
+            Shape::Some(x) => {
+                self.nullable = true;
+                self.merge(*x, name)?;
+            }
+            Shape::Atom(ty, _) => {
+                let is_compatible = match kind {
+                    DraftKind::Unknown => true,
+                    DraftKind::EmptyList => matches!(ty, AtomType::List(_)),
+                    DraftKind::EmptyMap => matches!(ty, AtomType::Map(..)),
+                    DraftKind::Atom(x) => *x == ty,
+                    DraftKind::Object(_) => false,
+                };
+                if !is_compatible {
+                    return Err(Error::mismatch(name));
+                }
+                *kind = DraftKind::Atom(ty);
+            }
+            Shape::EmptyList => match kind {
+                DraftKind::Unknown => *kind = DraftKind::EmptyList,
+                DraftKind::EmptyList | DraftKind::Atom(AtomType::List(_)) => (),
+                _ => return Err(Error::mismatch(name)),
+            },
+            Shape::EmptyMap => match kind {
+                DraftKind::Unknown => *kind = DraftKind::EmptyMap,
+                DraftKind::EmptyMap | DraftKind::Atom(AtomType::Map(..)) => (),
+                _ => return Err(Error::mismatch(name)),
+            },
+            Shape::Object(fields) => {
+                let is_first = matches!(kind, DraftKind::Unknown);
+                if is_first {
+                    *kind = DraftKind::Object(vec![]);
+                }
+                let DraftKind::Object(drafts) = kind else {
+                    return Err(Error::mismatch(name));
+                };
+                for (field, draft) in drafts.iter_mut() {
+                    if !fields.iter().any(|(x, _)| x == field) {
+                        draft.nullable = true;
+                    }
+                }
+                for (field, shape) in fields {
+                    let name = join(name, &field);
+                    let i = match drafts.iter().position(|(x, _)| *x == field) {
+                        Some(i) => i,
+                        None => {
+                            let draft = Draft {
+                                kind: DraftKind::Unknown,
+                                nullable: !is_first,
+                            };
+                            drafts.push((field, draft));
+                            drafts.len() - 1
+                        }
+                    };
+                    drafts[i].1.merge(shape, &name)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn into_field_type(self, name: &str) -> Result<FieldType, Error> {
+        Ok(match self.kind {
+            DraftKind::Unknown | DraftKind::EmptyList | DraftKind::EmptyMap => {
+                return Err(Error(format!(
+                    "type of field `{name}` is unknown without a non-null and non-empty value in the sample"
+                )));
+            }
+            DraftKind::Atom(ty) => FieldType::Atom(ty),
+            DraftKind::Object(fields) => FieldType::Object(object_scheme(fields, name)?),
+        })
+    }
+}
+fn object_scheme(fields: Vec<(String, Draft)>, name: &str) -> Result<ObjectScheme, Error> {
+    let fields = fields
+        .into_iter()
+        .map(|(field, draft)| {
+            let nullable = draft.nullable;
+            let ty = draft.into_field_type(&join(name, &field))?;
+            Ok(FieldScheme {
+                name: field,
+                ty,
+                nullable,
+                default: None,
+                aliases: vec![],
+                flatten: false,
+            })
+        })
+        .collect::<Result<_, Error>>()?;
+    Ok(ObjectScheme::new(fields))
+}
+
+/// Lay out the fields of an object in the columns of `object`
+fn push_values(
+    object: &ObjectScheme,
+    mut fields: Vec<(String, Shape)>,
+    name: &str,
+    values: &mut Vec<Option<AtomValue>>,
+) -> Result<(), Error> {
+    for field in &object.fields {
+        let field_name = join(name, &field.name);
+        let shape = match fields.iter().position(|(x, _)| *x == field.name) {
+            Some(i) => fields.swap_remove(i).1.into_inner(),
+            None => Shape::Null,
+        };
+        match (&field.ty, shape) {
+            (FieldType::Atom(_), Shape::Null) => values.push(None),
+            (FieldType::Atom(ty), shape) => values.push(Some(atom_value(ty, shape, &field_name)?)),
+            (FieldType::Object(nested), Shape::Null) => {
+                if field.nullable {
+                    values.push(Some(AtomValue::Bool(false)));
+                }
+                fill_nulls(nested, values);
+            }
+            (FieldType::Object(nested), Shape::Object(x)) => {
+                if field.nullable {
+                    values.push(Some(AtomValue::Bool(true)));
+                }
+                push_values(nested, x, &field_name, values)?;
+            }
+            (FieldType::Object(_), _) => return Err(Error::mismatch(&field_name)),
+        }
+    }
+    if let Some((field, _)) = fields.first() {
+        return Err(Error(format!(
+            "field `{}` is not in the scheme",
+            join(name, field)
+        )));
+    }
+    Ok(())
+}
+fn fill_nulls(object: &ObjectScheme, values: &mut Vec<Option<AtomValue>>) {
+    for field in &object.fields {
+        match &field.ty {
+            FieldType::Atom(_) => values.push(None),
+            FieldType::Object(nested) => {
+                if field.nullable {
+                    values.push(None);
+                }
+                fill_nulls(nested, values);
+            }
+        }
+    }
+}
+fn atom_value(ty: &AtomType, shape: Shape, name: &str) -> Result<AtomValue, Error> {
+    let value = match (ty, shape) {
+        (_, Shape::Atom(_, value)) => value,
+        (AtomType::List(item), Shape::EmptyList) => AtomValue::List(*item, Arc::from([])),
+        (AtomType::Map(key, value), Shape::EmptyMap) => AtomValue::Map(*key, *value, Arc::from([])),
+        _ => return Err(Error::mismatch(name)),
+    };
+    if !ty.is_type_of(&value) {
+        return Err(Error(format!(
+            "value `{value:?}` of field `{name}` is not of `{ty:?}`"
+        )));
+    }
+    Ok(value)
+}
+
+/// Items of lists and maps are non-null atoms of [`ItemType`]
+fn item_value(shape: Shape) -> Result<(ItemType, AtomValue), Error> {
+    let Shape::Atom(ty, value) = shape else {
+        return Err(Error(
+            "items of lists and maps are non-null atoms".to_string(),
+        ));
+    };
+    let ty = match ty {
+        AtomType::U8 | AtomType::U16 | AtomType::U32 => AtomType::U64,
+        AtomType::I8 | AtomType::I16 | AtomType::I32 => AtomType::I64,
+        x => x,
+    };
+    let item_type = ItemType::from_atom_type(&ty)
+        .ok_or_else(|| Error(format!("`{ty:?}` is not an item type")))?;
+    Ok((item_type, value))
+}
+/// Check that all items are of the type of the first
+fn check_item_type(ty: &mut Option<ItemType>, item_type: ItemType) -> Result<(), Error> {
+    if *ty.get_or_insert(item_type) != item_type {
+        return Err(Error(
+            "items of lists and maps are of the same type".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+struct ShapeSerializer;
+impl ser::Serializer for ShapeSerializer {
+    type Ok = Shape;
+    type Error = Error;
+    type SerializeSeq = ListShape;
+    type SerializeTuple = ObjectShape;
+    type SerializeTupleStruct = ObjectShape;
+    type SerializeTupleVariant = Impossible<Shape, Error>;
+    type SerializeMap = MapShape;
+    type SerializeStruct = ObjectShape;
+    type SerializeStructVariant = Impossible<Shape, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<Shape, Error> {
+        Ok(Shape::Atom(AtomType::Bool, AtomValue::Bool(v)))
+    }
+    fn serialize_i8(self, v: i8) -> Result<Shape, Error> {
+        Ok(Shape::Atom(AtomType::I8, AtomValue::I64(v.into())))
+    }
+    fn serialize_i16(self, v: i16) -> Result<Shape, Error> {
+        Ok(Shape::Atom(AtomType::I16, AtomValue::I64(v.into())))
+    }
+    fn serialize_i32(self, v: i32) -> Result<Shape, Error> {
+        Ok(Shape::Atom(AtomType::I32, AtomValue::I64(v.into())))
+    }
+    fn serialize_i64(self, v: i64) -> Result<Shape, Error> {
+        Ok(Shape::Atom(AtomType::I64, AtomValue::I64(v)))
+    }
+    fn serialize_i128(self, v: i128) -> Result<Shape, Error> {
+        Ok(Shape::Atom(AtomType::I128, AtomValue::I128(v)))
+    }
+    fn serialize_u8(self, v: u8) -> Result<Shape, Error> {
+        Ok(Shape::Atom(AtomType::U8, AtomValue::U64(v.into())))
+    }
+    fn serialize_u16(self, v: u16) -> Result<Shape, Error> {
+        Ok(Shape::Atom(AtomType::U16, AtomValue::U64(v.into())))
+    }
+    fn serialize_u32(self, v: u32) -> Result<Shape, Error> {
+        Ok(Shape::Atom(AtomType::U32, AtomValue::U64(v.into())))
+    }
+    fn serialize_u64(self, v: u64) -> Result<Shape, Error> {
+        Ok(Shape::Atom(AtomType::U64, AtomValue::U64(v)))
+    }
+    fn serialize_u128(self, v: u128) -> Result<Shape, Error> {
+        Ok(Shape::Atom(AtomType::U128, AtomValue::U128(v)))
+    }
+    fn serialize_f32(self, v: f32) -> Result<Shape, Error> {
+        Ok(Shape::Atom(AtomType::F32, AtomValue::F32(v)))
+    }
+    fn serialize_f64(self, v: f64) -> Result<Shape, Error> {
+        Ok(Shape::Atom(AtomType::F64, AtomValue::F64(v)))
+    }
+    fn serialize_char(self, v: char) -> Result<Shape, Error> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+    fn serialize_str(self, v: &str) -> Result<Shape, Error> {
+        Ok(Shape::Atom(AtomType::String, AtomValue::String(v.into())))
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<Shape, Error> {
+        Ok(Shape::Atom(AtomType::Bytes, AtomValue::Bytes(v.into())))
+    }
+    fn serialize_none(self) -> Result<Shape, Error> {
+        Ok(Shape::Null)
+    }
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Shape, Error> {
+        Ok(Shape::Some(Box::new(value.serialize(self)?)))
+    }
+    fn serialize_unit(self) -> Result<Shape, Error> {
+        Err(Error::unsupported("()"))
+    }
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Shape, Error> {
+        Err(Error::unsupported(name))
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Shape, Error> {
+        self.serialize_str(variant)
+    }
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Shape, Error> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _value: &T,
+    ) -> Result<Shape, Error> {
+        Err(Error::unsupported(format!("{name}::{variant}")))
+    }
+    fn serialize_seq(self, len: Option<usize>) -> Result<ListShape, Error> {
+        Ok(ListShape {
+            items: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+    fn serialize_tuple(self, len: usize) -> Result<ObjectShape, Error> {
+        Ok(ObjectShape {
+            fields: Vec::with_capacity(len),
+        })
+    }
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<ObjectShape, Error> {
+        self.serialize_tuple(len)
+    }
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(Error::unsupported(format!("{name}::{variant}")))
+    }
+    fn serialize_map(self, len: Option<usize>) -> Result<MapShape, Error> {
+        Ok(MapShape {
+            entries: Vec::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<ObjectShape, Error> {
+        self.serialize_tuple(len)
+    }
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(Error::unsupported(format!("{name}::{variant}")))
+    }
+}
+
+struct ListShape {
+    items: Vec<Shape>,
+}
+impl ser::SerializeSeq for ListShape {
+    type Ok = Shape;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.items.push(value.serialize(ShapeSerializer)?);
+        Ok(())
+    }
+    fn end(self) -> Result<Shape, Error> {
+        let mut ty = None;
+        let mut items = Vec::with_capacity(self.items.len());
+        for item in self.items {
+            let (item_type, item) = item_value(item)?;
+            check_item_type(&mut ty, item_type)?;
+            items.push(item);
+        }
+        let Some(ty) = ty else {
+            return Ok(Shape::EmptyList);
+        };
+        Ok(Shape::Atom(
+            AtomType::List(ty),
+            AtomValue::List(ty, items.into()),
+        ))
+    }
+}
+
+struct MapShape {
+    entries: Vec<(Shape, Shape)>,
+    key: Option<Shape>,
+}
+impl ser::SerializeMap for MapShape {
+    type Ok = Shape;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.key = Some(key.serialize(ShapeSerializer)?);
+        Ok(())
+    }
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error("map value without a key".to_string()))?;
+        self.entries.push((key, value.serialize(ShapeSerializer)?));
+        Ok(())
+    }
+    fn end(self) -> Result<Shape, Error> {
+        let (mut key_ty, mut value_ty) = (None, None);
+        let mut entries = Vec::with_capacity(self.entries.len());
+        for (key, value) in self.entries {
+            let (key_type, key) = item_value(key)?;
+            let (value_type, value) = item_value(value)?;
+            check_item_type(&mut key_ty, key_type)?;
+            check_item_type(&mut value_ty, value_type)?;
+            entries.push((key, value));
+        }
+        let (Some(key_ty), Some(value_ty)) = (key_ty, value_ty) else {
+            return Ok(Shape::EmptyMap);
+        };
+        Ok(Shape::Atom(
+            AtomType::Map(key_ty, value_ty),
+            AtomValue::Map(key_ty, value_ty, entries.into()),
+        ))
+    }
+}
+
+/// Fields of a struct or elements of a tuple named by their indices
+struct ObjectShape {
+    fields: Vec<(String, Shape)>,
+}
+impl ser::SerializeStruct for ObjectShape {
+    type Ok = Shape;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.fields
+            .push((key.to_string(), value.serialize(ShapeSerializer)?));
+        Ok(())
+    }
+    fn end(self) -> Result<Shape, Error> {
+        Ok(Shape::Object(self.fields))
+    }
+}
+impl ser::SerializeTuple for ObjectShape {
+    type Ok = Shape;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let name = self.fields.len().to_string();
+        self.fields.push((name, value.serialize(ShapeSerializer)?));
+        Ok(())
+    }
+    fn end(self) -> Result<Shape, Error> {
+        Ok(Shape::Object(self.fields))
+    }
+}
+impl ser::SerializeTupleStruct for ObjectShape {
+    type Ok = Shape;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeTuple::serialize_element(self, value)
+    }
+    fn end(self) -> Result<Shape, Error> {
+        ser::SerializeTuple::end(self)
+    }
+}
+
+/// Columns of a row looked up by name
+#[derive(Clone, Copy)]
+struct Columns<'a> {
+    header: &'a [AtomScheme],
+    values: &'a [Option<AtomValue>],
+}
+impl<'a> Columns<'a> {
+    fn get(&self, name: &str) -> Option<&'a Option<AtomValue>> {
+        let i = self.header.iter().position(|x| x.name == name)?;
+        self.values.get(i)
+    }
+
+    fn nested<'b>(self, name: &'b str) -> impl Iterator<Item = &'a Option<AtomValue>> + 'b
+    where
+        'a: 'b,
+    {
+        self.header
+            .iter()
+            .zip(self.values)
+            .filter(move |(x, _)| {
+                name.is_empty()
+                    || x.name
+                        .strip_prefix(name)
+                        .is_some_and(|x| x.starts_with(SEPARATOR))
+            })
+            .map(|(_, x)| x)
+    }
+
+    /// Whether the field has a column or nested columns
+    fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some() || self.nested(name).next().is_some()
+    }
+}
+
+/// Deserialize the field of the full name `name` from `columns`
+struct FieldDeserializer<'a> {
+    columns: Columns<'a>,
+    name: String,
+}
+impl<'de> de::Deserializer<'de> for FieldDeserializer<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.columns.get(&self.name) {
+            Some(Some(value)) => visit_atom(value, visitor),
+            Some(None) => visitor.visit_none(),
+            None => Err(Error(format!("column `{}` is missing", self.name))),
+        }
+    }
+
+    /// An object is present if its validity column is `true` or, without one, if any nested column is non-null.
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let is_object = self.columns.nested(&self.name).next().is_some();
+        let is_some = match self.columns.get(&self.name) {
+            Some(Some(AtomValue::Bool(x))) if is_object => *x,
+            Some(value) => value.is_some(),
+            None => self.columns.nested(&self.name).any(|x| x.is_some()),
+        };
+        if is_some {
+            visitor.visit_some(self)
+        } else {
+            visitor.visit_none()
+        }
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple<V: de::Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_seq(TupleAccess {
+            columns: self.columns,
+            name: self.name,
+            index: 0,
+            len,
+        })
+    }
+
+    fn deserialize_tuple_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_map(StructAccess {
+            columns: self.columns,
+            name: self.name,
+            fields: fields.iter(),
+            field: None,
+        })
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.columns.get(&self.name) {
+            Some(Some(value)) => AtomDeserializer(value).deserialize_enum(name, variants, visitor),
+            _ => Err(Error(format!("column `{}` is missing", self.name))),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq map identifier ignored_any
+    }
+}
+
+/// Fields of a struct skipping those without columns
+struct StructAccess<'a> {
+    columns: Columns<'a>,
+    name: String,
+    fields: std::slice::Iter<'static, &'static str>,
+    field: Option<String>,
+}
+impl<'de> de::MapAccess<'de> for StructAccess<'_> {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        for &field in self.fields.by_ref() {
+            let name = join(&self.name, field);
+            if !self.columns.contains(&name) {
+                continue;
+            }
+            self.field = Some(name);
+            return seed.deserialize(field.into_deserializer()).map(Some);
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let name = self
+            .field
+            .take()
+            .ok_or_else(|| Error("struct value without a key".to_string()))?;
+        seed.deserialize(FieldDeserializer {
+            columns: self.columns,
+            name,
+        })
+    }
+}
+
+/// Elements of a tuple named by their indices
+struct TupleAccess<'a> {
+    columns: Columns<'a>,
+    name: String,
+    index: usize,
+    len: usize,
+}
+impl<'de> de::SeqAccess<'de> for TupleAccess<'_> {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        if self.index == self.len {
+            return Ok(None);
+        }
+        let name = join(&self.name, &self.index.to_string());
+        self.index += 1;
+        seed.deserialize(FieldDeserializer {
+            columns: self.columns,
+            name,
+        })
+        .map(Some)
+    }
+}
+
+/// Deserialize a non-null atom or an item of it
+struct AtomDeserializer<'a>(&'a AtomValue);
+impl<'de> IntoDeserializer<'de, Error> for AtomDeserializer<'_> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+impl<'de> de::Deserializer<'de> for AtomDeserializer<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visit_atom(self.0, visitor)
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Unit variants are strings of their names.
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let AtomValue::String(variant) = self.0 else {
+            return Err(Error(format!("`{:?}` is not a variant", self.0)));
+        };
+        visitor.visit_enum(variant.as_ref().into_deserializer())
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+fn visit_atom<'de, V: de::Visitor<'de>>(value: &AtomValue, visitor: V) -> Result<V::Value, Error> {
+    match value {
+        AtomValue::String(x) => visitor.visit_str(x),
+        AtomValue::Bytes(x) => visitor.visit_bytes(x),
+        AtomValue::U64(x) => visitor.visit_u64(*x),
+        AtomValue::I64(x) => visitor.visit_i64(*x),
+        AtomValue::F32(x) => visitor.visit_f32(*x),
+        AtomValue::F64(x) => visitor.visit_f64(*x),
+        AtomValue::Bool(x) => visitor.visit_bool(*x),
+        AtomValue::List(_, items) => {
+            let mut seq = de::value::SeqDeserializer::new(items.iter().map(AtomDeserializer));
+            let value = visitor.visit_seq(&mut seq)?;
+            seq.end()?;
+            Ok(value)
+        }
+        AtomValue::Map(_, _, entries) => {
+            let entries = entries
+                .iter()
+                .map(|(k, v)| (AtomDeserializer(k), AtomDeserializer(v)));
+            let mut map = de::value::MapDeserializer::new(entries);
+            let value = visitor.visit_map(&mut map)?;
+            map.end()?;
+            Ok(value)
+        }
+        AtomValue::Timestamp(x) | AtomValue::Duration(x) => visitor.visit_i64(*x),
+        AtomValue::Date(x) => visitor.visit_i32(*x),
+        AtomValue::U128(x) => visitor.visit_u128(*x),
+        AtomValue::I128(x) | AtomValue::Decimal(x) => visitor.visit_i128(*x),
+        AtomValue::Uuid(x) => visitor.visit_bytes(x),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::Deserialize;
+
+    use crate::io::{
+        bin::{HdvBinRawReader, HdvBinWriter},
+        text::{HdvTextRawReader, HdvTextWriter, HdvTextWriterOptions},
+    };
+
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Order {
+        id: u32,
+        customer: Option<Customer>,
+        tags: Vec<String>,
+        prices: BTreeMap<String, f64>,
+        status: Status,
+        note: Option<String>,
+    }
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Customer {
+        name: String,
+        phone: Option<u64>,
+    }
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    enum Status {
+        Open,
+        Closed,
+    }
+
+    #[test]
+    fn test_serde_bridge() {
+        let orders = [
+            Order {
+                id: 1,
+                customer: None,
+                tags: vec![],
+                prices: BTreeMap::new(),
+                status: Status::Open,
+                note: None,
+            },
+            Order {
+                id: 2,
+                customer: Some(Customer {
+                    name: "a".into(),
+                    phone: Some(5),
+                }),
+                tags: vec!["x".into(), "y".into()],
+                prices: BTreeMap::from([("b".into(), 1.5)]),
+                status: Status::Closed,
+                note: Some("c".into()),
+            },
+        ];
+        assert!(SerdeBridge::from_value(&orders[0]).is_err());
+        let bridge = SerdeBridge::from_sample(&orders).unwrap();
+        let names = bridge
+            .object_scheme()
+            .atom_schemes()
+            .into_iter()
+            .map(|x| (x.name, x.r#type))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                ("id".to_string(), AtomType::U32),
+                ("customer".to_string(), AtomType::Bool),
+                ("customer.name".to_string(), AtomType::String),
+                ("customer.phone".to_string(), AtomType::U64),
+                ("tags".to_string(), AtomType::List(ItemType::String)),
+                (
+                    "prices".to_string(),
+                    AtomType::Map(ItemType::String, ItemType::F64)
+                ),
+                ("status".to_string(), AtomType::String),
+                ("note".to_string(), AtomType::String),
+            ]
+        );
+
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        for order in &orders {
+            writer.write(&bridge.record(order).unwrap()).unwrap();
+        }
+        writer.flush().unwrap();
+        let mut reader = HdvBinRawReader::new(std::io::Cursor::new(&buf));
+        for order in &orders {
+            let row = reader.read().unwrap();
+            let header = reader.header().unwrap();
+            let order_: Order = from_values(header, row.atoms()).unwrap();
+            assert_eq!(order, &order_);
+        }
+
+        let mut buf = vec![];
+        let options = HdvTextWriterOptions {
+            is_csv_header: false,
+        };
+        let mut writer = HdvTextWriter::new(&mut buf, options);
+        for order in &orders {
+            writer.write(&bridge.record(order).unwrap()).unwrap();
+        }
+        writer.flush().unwrap();
+        let mut reader = HdvTextRawReader::new(std::io::Cursor::new(&buf));
+        for order in &orders {
+            let row = reader.read().unwrap();
+            let header = reader.header().unwrap();
+            let order_: Order = from_values(header, row.atoms()).unwrap();
+            assert_eq!(order, &order_);
+        }
+
+        #[derive(Debug, Serialize)]
+        struct Other {
+            id: i64,
+        }
+        assert!(bridge.to_values(&Other { id: 1 }).is_err());
+        assert!(SerdeBridge::from_value(&1_u64).is_err());
+    }
+}
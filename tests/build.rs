@@ -0,0 +1,49 @@
+use hdv::{
+    codegen::{generate_file, HeaderFormat, RustCodegenOptions},
+    format::{AtomScheme, AtomType, AtomValue, ValueRow},
+    io::bin::HdvBinRawWriter,
+};
+
+/// Write a file of another team and generate its structs for `test_codegen`.
+fn main() {
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    let input = out_dir.join("users.hdv");
+
+    let header = [
+        ("id", AtomType::U64),
+        ("name", AtomType::String),
+        ("contact", AtomType::Bool),
+        ("contact.email", AtomType::String),
+        ("contact.phoneNumber", AtomType::U32),
+        ("pos.x", AtomType::F64),
+    ]
+    .map(|(name, r#type)| AtomScheme {
+        name: name.to_string(),
+        r#type,
+    });
+    let mut file = std::fs::File::create(&input).unwrap();
+    let mut writer = HdvBinRawWriter::new(&mut file, header.to_vec());
+    let row = ValueRow::new(vec![
+        Some(AtomValue::U64(1)),
+        Some(AtomValue::String("a".into())),
+        Some(AtomValue::Bool(true)),
+        Some(AtomValue::String("a@b.c".into())),
+        None,
+        Some(AtomValue::F64(1.5)),
+    ]);
+    writer.write(&row).unwrap();
+    writer.flush().unwrap();
+
+    let options = RustCodegenOptions {
+        root_name: "User".to_string(),
+        ..Default::default()
+    };
+    generate_file(
+        &input,
+        HeaderFormat::Bin,
+        out_dir.join("users.rs"),
+        &options,
+    )
+    .unwrap();
+    println!("cargo:rerun-if-changed=build.rs");
+}
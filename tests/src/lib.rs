@@ -1,14 +1,24 @@
 #[cfg(test)]
 mod tests {
-    use std::sync::Arc;
+    use std::{
+        collections::{BTreeMap, HashMap},
+        sync::Arc,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    };
 
     use hdv::{
-        format::AtomValue,
+        dynamic::{DynamicRecord, DynamicScheme},
+        format::{AtomScheme, AtomType, AtomValue, ItemType, TimeUnit, ValueRow},
         io::{
-            bin::{HdvBinReader, HdvBinWriter},
-            text::{HdvTextReader, HdvTextWriter, HdvTextWriterOptions},
+            bin::{HdvBinRawReader, HdvBinRawWriter, HdvBinReader, HdvBinWriter},
+            multi::{HdvBinMultiReader, HdvBinMultiWriter},
+            text::{
+                HdvTextRawReader, HdvTextRawWriter, HdvTextReader, HdvTextWriter,
+                HdvTextWriterOptions,
+            },
+            HdvReaderOptions,
         },
-        serde::{HdvDeserialize, HdvSerialize},
+        serde::{FieldScheme, FieldType, HdvDeserialize, HdvScheme, HdvSerialize, ObjectScheme},
     };
     use hdv_derive::HdvSerde;
 
@@ -47,6 +57,7 @@ mod tests {
             values,
             [
                 Some(AtomValue::U64(1)),
+                Some(AtomValue::Bool(false)),
                 None,
                 None,
                 None,
@@ -114,6 +125,7 @@ mod tests {
             values,
             [
                 Some(AtomValue::U64(1)),
+                Some(AtomValue::Bool(false)),
                 None,
                 None,
                 Some(AtomValue::F64(3.0)),
@@ -148,4 +160,1615 @@ mod tests {
         assert_eq!(a.a, partial_a.a);
         assert_eq!(a.c, partial_a.c);
     }
+
+    #[test]
+    fn test_derive_bin_multi() {
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct Login {
+            user: Arc<str>,
+            at: u64,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct Logout {
+            at: u64,
+            reason: Option<Arc<str>>,
+        }
+        #[derive(Debug, PartialEq)]
+        enum Event {
+            Login(Login),
+            Logout(Logout),
+        }
+
+        let events = [
+            Event::Login(Login {
+                user: "a".into(),
+                at: 1,
+            }),
+            Event::Logout(Logout {
+                at: 2,
+                reason: None,
+            }),
+            Event::Login(Login {
+                user: "b".into(),
+                at: 3,
+            }),
+            Event::Logout(Logout {
+                at: 4,
+                reason: Some("timeout".into()),
+            }),
+        ];
+
+        let mut buf = vec![];
+        let mut writer = HdvBinMultiWriter::new(&mut buf);
+        writer.register::<Login>(0);
+        writer.register::<Logout>(1);
+        for event in &events {
+            match event {
+                Event::Login(x) => writer.write(0, x).unwrap(),
+                Event::Logout(x) => writer.write(1, x).unwrap(),
+            }
+        }
+        writer.flush().unwrap();
+
+        let mut reader = HdvBinMultiReader::new(std::io::Cursor::new(&buf));
+        reader.register(0, Event::Login);
+        reader.register(1, Event::Logout);
+        for event in &events {
+            let event_: Event = reader.read().unwrap();
+            assert_eq!(*event, event_);
+        }
+
+        let mut reader = HdvBinMultiReader::new(std::io::Cursor::new(&buf));
+        reader.register(1, |x: Logout| x.at);
+        assert_eq!(reader.read().unwrap(), 2);
+        assert_eq!(reader.read().unwrap(), 4);
+        assert!(reader.read().is_err());
+    }
+
+    #[test]
+    fn test_derive_schema_evolution() {
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct V1 {
+            a: u16,
+            b: Option<B>,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct V2 {
+            a: u16,
+            b: Option<B>,
+            c: Option<f64>,
+            d: Option<B>,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct B {
+            a: i64,
+            b: Option<Arc<str>>,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct V3 {
+            a: u16,
+            e: B,
+        }
+
+        let v1 = V1 {
+            a: 1,
+            b: Some(B { a: 2, b: None }),
+        };
+
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        writer.write(&v1).unwrap();
+        writer.flush().unwrap();
+
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        let v2: V2 = reader.read().unwrap();
+        assert_eq!(
+            v2,
+            V2 {
+                a: 1,
+                b: Some(B { a: 2, b: None }),
+                c: None,
+                d: None,
+            }
+        );
+
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        let e = reader.read().map(|_: V3| ()).unwrap_err();
+        assert_eq!(e.to_string(), "column `e.a` is missing");
+    }
+
+    #[test]
+    fn test_derive_coercion() {
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct V1 {
+            a: u64,
+            b: f32,
+            c: u64,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct V2 {
+            a: i64,
+            b: f64,
+            c: Arc<str>,
+        }
+
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        writer.write(&V1 { a: 1, b: 0.5, c: 2 }).unwrap();
+        writer
+            .write(&V1 {
+                a: u64::MAX,
+                b: 0.5,
+                c: 2,
+            })
+            .unwrap();
+        writer.flush().unwrap();
+
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        let e = reader.read().map(|_: V2| ()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "column `c` is of type `U64` which cannot be read as `String`"
+        );
+
+        let options = HdvReaderOptions {
+            coerce_to_string: true,
+            ..Default::default()
+        };
+        let mut reader = HdvBinReader::with_options(std::io::Cursor::new(&buf), options);
+        let v2: V2 = reader.read().unwrap();
+        assert_eq!(
+            v2,
+            V2 {
+                a: 1,
+                b: 0.5,
+                c: "2".into(),
+            }
+        );
+        let e = reader.read().map(|_: V2| ()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "column `a`: value `U64(18446744073709551615)` cannot be coerced"
+        );
+
+        // Coerced strings are not in the row buffer
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct V3<'a> {
+            c: &'a str,
+        }
+        let options = HdvReaderOptions {
+            coerce_to_string: true,
+            ..Default::default()
+        };
+        let mut reader =
+            HdvBinReader::<_, V3<'static>>::with_options(std::io::Cursor::new(&buf), options);
+        let e = reader.read_borrowed().map(|_| ()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "column `c` cannot be coerced into a borrowed string"
+        );
+    }
+
+    #[test]
+    fn test_derive_alias() {
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct V1 {
+            a: u16,
+            d: B1,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct B1 {
+            c: Arc<str>,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct V2 {
+            #[hdv(alias = "a")]
+            x: u16,
+            #[hdv(alias = "d")]
+            y: B2,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct B2 {
+            #[hdv(alias = "b", alias = "c")]
+            z: Arc<str>,
+        }
+
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        writer
+            .write(&V1 {
+                a: 1,
+                d: B1 { c: "c".into() },
+            })
+            .unwrap();
+        writer.flush().unwrap();
+
+        let v2 = V2 {
+            x: 1,
+            y: B2 { z: "c".into() },
+        };
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        let v2_: V2 = reader.read().unwrap();
+        assert_eq!(v2, v2_);
+
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct V3 {
+            x: u16,
+            d: B1,
+        }
+        let options = HdvReaderOptions {
+            aliases: [("a".to_string(), "x".to_string())].into(),
+            ..Default::default()
+        };
+        let mut reader = HdvBinReader::with_options(std::io::Cursor::new(&buf), options);
+        let v3: V3 = reader.read().unwrap();
+        assert_eq!(
+            v3,
+            V3 {
+                x: 1,
+                d: B1 { c: "c".into() },
+            }
+        );
+    }
+
+    #[test]
+    fn test_derive_bool() {
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct A {
+            a: bool,
+            b: Option<bool>,
+            c: Option<bool>,
+        }
+
+        let a = A {
+            a: true,
+            b: Some(false),
+            c: None,
+        };
+
+        let mut values = vec![];
+        a.serialize(&mut values);
+        assert_eq!(
+            values,
+            [
+                Some(AtomValue::Bool(true)),
+                Some(AtomValue::Bool(false)),
+                None,
+            ]
+        );
+
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        writer.write(&a).unwrap();
+        writer.flush().unwrap();
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        let a_: A = reader.read().unwrap();
+        assert_eq!(a, a_);
+
+        let mut buf = vec![];
+        let options = HdvTextWriterOptions {
+            is_csv_header: false,
+        };
+        let mut writer = HdvTextWriter::new(&mut buf, options);
+        writer.write(&a).unwrap();
+        writer.flush().unwrap();
+        let mut reader = HdvTextReader::new(std::io::Cursor::new(&buf));
+        let a_: A = reader.read().unwrap();
+        assert_eq!(a, a_);
+    }
+
+    #[test]
+    fn test_derive_type_paths() {
+        extern crate alloc;
+
+        type Id = u32;
+        type Name = std::sync::Arc<str>;
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct A {
+            a: std::sync::Arc<str>,
+            b: alloc::sync::Arc<[u8]>,
+            c: Box<str>,
+            d: std::rc::Rc<str>,
+            e: String,
+            f: Option<String>,
+            g: Vec<u8>,
+            h: Option<Box<[u8]>>,
+            i: usize,
+            j: isize,
+            k: Id,
+            l: Option<Name>,
+            m: core::primitive::u8,
+        }
+
+        let a = A {
+            a: "a".into(),
+            b: b"b".as_ref().into(),
+            c: "c".into(),
+            d: "d".into(),
+            e: "e".into(),
+            f: Some("f".into()),
+            g: b"g".to_vec(),
+            h: None,
+            i: 1,
+            j: -1,
+            k: 2,
+            l: Some("l".into()),
+            m: 3,
+        };
+
+        let mut values = vec![];
+        a.serialize(&mut values);
+        assert_eq!(
+            values,
+            [
+                Some(AtomValue::String("a".into())),
+                Some(AtomValue::Bytes(b"b".as_ref().into())),
+                Some(AtomValue::String("c".into())),
+                Some(AtomValue::String("d".into())),
+                Some(AtomValue::String("e".into())),
+                Some(AtomValue::String("f".into())),
+                Some(AtomValue::Bytes(b"g".as_ref().into())),
+                None,
+                Some(AtomValue::U64(1)),
+                Some(AtomValue::I64(-1)),
+                Some(AtomValue::U64(2)),
+                Some(AtomValue::String("l".into())),
+                Some(AtomValue::U64(3)),
+            ]
+        );
+
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        writer.write(&a).unwrap();
+        writer.flush().unwrap();
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        let a_: A = reader.read().unwrap();
+        assert_eq!(a, a_);
+    }
+
+    #[test]
+    fn test_derive_field_attrs() {
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct V1 {
+            a: u16,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct V2 {
+            #[hdv(rename = "a")]
+            x: u16,
+            #[hdv(skip)]
+            y: Vec<u8>,
+            #[hdv(default)]
+            b: i64,
+            #[hdv(default = "default_c")]
+            c: Option<Arc<str>>,
+            #[hdv(default = "default_d")]
+            d: B,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct B {
+            a: f64,
+            b: Option<bool>,
+        }
+        fn default_c() -> Option<Arc<str>> {
+            Some("c".into())
+        }
+        fn default_d() -> B {
+            B { a: 0.5, b: None }
+        }
+
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        writer.write(&V1 { a: 1 }).unwrap();
+        writer.flush().unwrap();
+
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        let v2: V2 = reader.read().unwrap();
+        let v2_ = V2 {
+            x: 1,
+            y: vec![],
+            b: 0,
+            c: Some("c".into()),
+            d: B { a: 0.5, b: None },
+        };
+        assert_eq!(v2, v2_);
+
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        writer
+            .write(&V2 {
+                y: b"y".to_vec(),
+                ..v2_
+            })
+            .unwrap();
+        writer.flush().unwrap();
+
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        let v1: V1 = reader.read().unwrap();
+        assert_eq!(v1, V1 { a: 1 });
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        let v2: V2 = reader.read().unwrap();
+        assert_eq!(v2.y, b"");
+
+        // Defaults of nullable objects
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct V3 {
+            a: u16,
+            #[hdv(default = "default_e")]
+            e: Option<B>,
+            #[hdv(default)]
+            f: Option<B>,
+        }
+        fn default_e() -> Option<B> {
+            Some(B {
+                a: 7.,
+                b: Some(true),
+            })
+        }
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        writer.write(&V1 { a: 1 }).unwrap();
+        writer.flush().unwrap();
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        let v3: V3 = reader.read().unwrap();
+        assert_eq!(
+            v3,
+            V3 {
+                a: 1,
+                e: default_e(),
+                f: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_derive_flatten() {
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct Metadata {
+            id: u64,
+            tags: Option<B>,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        #[hdv(separator = "_")]
+        struct A {
+            a: u16,
+            #[hdv(flatten)]
+            meta: Metadata,
+            b: B,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct B {
+            x: Arc<str>,
+        }
+
+        let names = A::object_scheme()
+            .atom_schemes()
+            .into_iter()
+            .map(|x| x.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["a", "id", "tags", "tags.x", "b_x"]);
+
+        let a = A {
+            a: 1,
+            meta: Metadata { id: 2, tags: None },
+            b: B { x: "x".into() },
+        };
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        writer.write(&a).unwrap();
+        writer.flush().unwrap();
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        let a_: A = reader.read().unwrap();
+        assert_eq!(a, a_);
+    }
+
+    #[test]
+    #[should_panic(expected = "column `id` is defined more than once")]
+    fn test_derive_flatten_collision() {
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct Metadata {
+            id: u64,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct A {
+            id: u64,
+            #[hdv(flatten)]
+            meta: Metadata,
+        }
+
+        A::object_scheme();
+    }
+
+    #[test]
+    fn test_derive_enum() {
+        #[derive(Debug, HdvSerde, PartialEq)]
+        enum Status {
+            Active,
+            Banned,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        #[hdv(repr = "u64")]
+        enum Code {
+            Ok = 200,
+            NotFound = 404,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        #[hdv(tag = "kind")]
+        enum Event {
+            Login { user: Arc<str>, at: u64 },
+            Logout { at: u64, status: Option<Status> },
+            Ping,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct A {
+            status: Status,
+            code: Option<Code>,
+            event: Event,
+        }
+
+        let names = A::object_scheme()
+            .atom_schemes()
+            .into_iter()
+            .map(|x| x.name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "status",
+                "code",
+                "event.kind",
+                "event.Login.user",
+                "event.Login.at",
+                "event.Logout.at",
+                "event.Logout.status",
+            ]
+        );
+
+        let rows = [
+            A {
+                status: Status::Active,
+                code: Some(Code::NotFound),
+                event: Event::Login {
+                    user: "a".into(),
+                    at: 1,
+                },
+            },
+            A {
+                status: Status::Banned,
+                code: None,
+                event: Event::Logout {
+                    at: 2,
+                    status: Some(Status::Banned),
+                },
+            },
+            A {
+                status: Status::Active,
+                code: Some(Code::Ok),
+                event: Event::Ping,
+            },
+        ];
+
+        let mut values = vec![];
+        rows[0].serialize(&mut values);
+        assert_eq!(
+            values,
+            [
+                Some(AtomValue::String("Active".into())),
+                Some(AtomValue::U64(404)),
+                Some(AtomValue::String("Login".into())),
+                Some(AtomValue::String("a".into())),
+                Some(AtomValue::U64(1)),
+                None,
+                None,
+            ]
+        );
+        // Fields of variants are null unless their variant is active
+        let FieldType::Object(event) = &A::object_scheme().fields[2].ty else {
+            unreachable!()
+        };
+        assert!(!event.fields[1].nullable);
+        let FieldType::Object(login) = &event.fields[1].ty else {
+            unreachable!()
+        };
+        assert!(login.fields.iter().all(|x| x.nullable));
+
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        for row in &rows {
+            writer.write(row).unwrap();
+        }
+        writer.flush().unwrap();
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        for row in &rows {
+            let row_: A = reader.read().unwrap();
+            assert_eq!(*row, row_);
+        }
+
+        let mut buf = vec![];
+        let options = HdvTextWriterOptions {
+            is_csv_header: false,
+        };
+        let mut writer = HdvTextWriter::new(&mut buf, options);
+        for row in &rows {
+            writer.write(row).unwrap();
+        }
+        writer.flush().unwrap();
+        let mut reader = HdvTextReader::new(std::io::Cursor::new(&buf));
+        for row in &rows {
+            let row_: A = reader.read().unwrap();
+            assert_eq!(*row, row_);
+        }
+    }
+
+    #[test]
+    fn test_derive_tuple_struct() {
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct UserId(u64);
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct Name(Arc<str>);
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct Point(f64, f64);
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct Wrapper(Point);
+        #[derive(Debug, HdvSerde, PartialEq)]
+        enum Shape {
+            Circle(Point, f64),
+            Empty,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct A {
+            id: UserId,
+            name: Option<Name>,
+            at: Wrapper,
+            shape: Shape,
+        }
+
+        let names = A::object_scheme()
+            .atom_schemes()
+            .into_iter()
+            .map(|x| x.name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "id",
+                "name",
+                "at.0",
+                "at.1",
+                "shape.tag",
+                "shape.Circle.0.0",
+                "shape.Circle.0.1",
+                "shape.Circle.1",
+            ]
+        );
+        assert_eq!(
+            UserId::object_scheme().atom_schemes(),
+            u64::object_scheme().atom_schemes()
+        );
+
+        let a = A {
+            id: UserId(1),
+            name: Some(Name("a".into())),
+            at: Wrapper(Point(0.5, 1.5)),
+            shape: Shape::Circle(Point(0., 0.), 2.),
+        };
+        let mut values = vec![];
+        a.serialize(&mut values);
+        assert_eq!(
+            values[..2],
+            [Some(AtomValue::U64(1)), Some(AtomValue::String("a".into()))]
+        );
+
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        writer.write(&a).unwrap();
+        writer.flush().unwrap();
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        let a_: A = reader.read().unwrap();
+        assert_eq!(a, a_);
+
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        writer.write(&UserId(2)).unwrap();
+        writer.flush().unwrap();
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        let id: u64 = reader.read().unwrap();
+        assert_eq!(id, 2);
+    }
+
+    #[test]
+    fn test_derive_generics() {
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct Envelope<T, M = u64>
+        where
+            T: std::fmt::Debug,
+        {
+            ts: u64,
+            body: T,
+            meta: Option<M>,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct Login {
+            user: Arc<str>,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        enum Either<L, R> {
+            Left { value: L },
+            Right { value: R },
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct Wrapper<T>(T);
+
+        let envelope = Envelope {
+            ts: 1,
+            body: Login { user: "a".into() },
+            meta: Some(Either::<u64, Wrapper<Arc<str>>>::Right {
+                value: Wrapper("b".into()),
+            }),
+        };
+        let names = Envelope::<Login, Either<u64, Wrapper<Arc<str>>>>::object_scheme()
+            .atom_schemes()
+            .into_iter()
+            .map(|x| x.name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "ts",
+                "body.user",
+                "meta",
+                "meta.tag",
+                "meta.Left.value",
+                "meta.Right.value",
+            ]
+        );
+
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        writer.write(&envelope).unwrap();
+        writer.flush().unwrap();
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        let envelope_: Envelope<_, _> = reader.read().unwrap();
+        assert_eq!(envelope, envelope_);
+    }
+
+    #[test]
+    fn test_derive_borrowed() {
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct View<'a> {
+            name: &'a str,
+            blob: &'a [u8],
+            n: u32,
+            label: Option<&'a str>,
+            owned: String,
+            inner: Inner<'a>,
+            shape: Shape,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct Inner<'a>(&'a str, Option<Arc<str>>);
+        #[derive(Debug, HdvSerde, PartialEq)]
+        #[hdv(borrow)]
+        enum Shape {
+            Circle { r: f64 },
+            Label(Box<str>),
+        }
+
+        let rows = [
+            View {
+                name: "a",
+                blob: b"xy",
+                n: 1,
+                label: None,
+                owned: "o".to_string(),
+                inner: Inner("i", Some("j".into())),
+                shape: Shape::Circle { r: 1. },
+            },
+            View {
+                name: "bb",
+                blob: b"",
+                n: 2,
+                label: Some("l"),
+                owned: "p".to_string(),
+                inner: Inner("k", None),
+                shape: Shape::Label("s".into()),
+            },
+        ];
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        for row in &rows {
+            writer.write(row).unwrap();
+        }
+        writer.flush().unwrap();
+
+        let mut reader = HdvBinReader::<_, View<'static>>::new(std::io::Cursor::new(&buf));
+        for row in &rows {
+            let view = reader.read_borrowed().unwrap();
+            assert_eq!(&view, row);
+        }
+        assert!(reader.read_borrowed().is_err());
+
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct PartialView<'a> {
+            n: f64,
+            name: &'a str,
+        }
+        let options = HdvReaderOptions {
+            coerce_int_to_float: true,
+            ..Default::default()
+        };
+        let mut reader = HdvBinReader::<_, PartialView<'static>>::with_options(
+            std::io::Cursor::new(&buf),
+            options,
+        );
+        let view = reader.read_borrowed().unwrap();
+        assert_eq!(view, PartialView { n: 1., name: "a" });
+    }
+
+    #[test]
+    fn test_derive_owned_only_object() {
+        /// Implements only the owned deserializer
+        #[derive(Debug, PartialEq)]
+        struct B {
+            x: u64,
+        }
+        impl HdvScheme for B {
+            fn object_scheme() -> ObjectScheme {
+                ObjectScheme::new(vec![FieldScheme {
+                    name: "x".to_string(),
+                    ty: FieldType::Atom(AtomType::U64),
+                    nullable: false,
+                    default: None,
+                    aliases: vec![],
+                    flatten: false,
+                }])
+            }
+        }
+        impl HdvSerialize for B {
+            fn serialize(&self, values: &mut Vec<Option<AtomValue>>) {
+                self.x.serialize(values);
+            }
+            fn fill_nulls(values: &mut Vec<Option<AtomValue>>) {
+                values.push(None);
+            }
+        }
+        impl HdvDeserialize for B {
+            fn deserialize(values: &mut &[Option<AtomValue>]) -> Option<Self> {
+                Some(Self {
+                    x: u64::deserialize(values)?,
+                })
+            }
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct A {
+            b: B,
+        }
+
+        let a = A { b: B { x: 1 } };
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        writer.write(&a).unwrap();
+        writer.flush().unwrap();
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        let a_: A = reader.read().unwrap();
+        assert_eq!(a, a_);
+    }
+
+    #[test]
+    fn test_derive_list() {
+        #[derive(Debug, HdvSerde, PartialEq)]
+        #[hdv(borrow)]
+        struct Event {
+            id: u32,
+            tags: Vec<Arc<str>>,
+            bins: Vec<u32>,
+            weights: Option<Vec<f64>>,
+            flags: Vec<bool>,
+        }
+
+        let events = [
+            Event {
+                id: 1,
+                tags: vec!["a".into(), "b".into()],
+                bins: vec![1, 2, 3],
+                weights: None,
+                flags: vec![],
+            },
+            Event {
+                id: 2,
+                tags: vec![],
+                bins: vec![4],
+                weights: Some(vec![0.5]),
+                flags: vec![true, false],
+            },
+        ];
+        assert_eq!(
+            Event::object_scheme().atom_schemes()[1].r#type,
+            AtomType::List(ItemType::String)
+        );
+
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        for event in &events {
+            writer.write(event).unwrap();
+        }
+        writer.flush().unwrap();
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        for event in &events {
+            let event_: Event = reader.read().unwrap();
+            assert_eq!(event, &event_);
+        }
+        let mut reader = HdvBinReader::<_, Event>::new(std::io::Cursor::new(&buf));
+        for event in &events {
+            assert_eq!(event, &reader.read_borrowed().unwrap());
+        }
+
+        let mut buf = vec![];
+        let options = HdvTextWriterOptions {
+            is_csv_header: false,
+        };
+        let mut writer = HdvTextWriter::new(&mut buf, options);
+        for event in &events {
+            writer.write(event).unwrap();
+        }
+        writer.flush().unwrap();
+        let text = String::from_utf8(buf.clone()).unwrap();
+        assert!(text.ends_with("1,[a;b],[1;2;3],,[],\n2,[],[4],[0.5],[true;false],\n"));
+        let mut reader = HdvTextReader::new(std::io::Cursor::new(&buf));
+        for event in &events {
+            let event_: Event = reader.read().unwrap();
+            assert_eq!(event, &event_);
+        }
+    }
+
+    #[test]
+    fn test_derive_temporal() {
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct Run {
+            started_at: SystemTime,
+            elapsed: Duration,
+            finished_at: Option<SystemTime>,
+        }
+
+        let runs = [
+            Run {
+                started_at: UNIX_EPOCH + Duration::from_millis(1_500),
+                elapsed: Duration::from_micros(2_250),
+                finished_at: None,
+            },
+            Run {
+                started_at: UNIX_EPOCH - Duration::from_secs(1),
+                elapsed: Duration::ZERO,
+                finished_at: Some(UNIX_EPOCH + Duration::from_secs(86_400)),
+            },
+            Run {
+                // Beyond the range of `i64` nanoseconds
+                started_at: UNIX_EPOCH + Duration::from_secs(400 * 365 * 86_400),
+                elapsed: Duration::from_secs(400 * 365 * 86_400),
+                finished_at: None,
+            },
+        ];
+        assert_eq!(
+            Run::object_scheme().atom_schemes()[0].r#type,
+            AtomType::Timestamp {
+                unit: TimeUnit::Microseconds,
+                tz: Some("UTC".into()),
+            }
+        );
+
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        for run in &runs {
+            writer.write(run).unwrap();
+        }
+        writer.flush().unwrap();
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        for run in &runs {
+            let run_: Run = reader.read().unwrap();
+            assert_eq!(run, &run_);
+        }
+
+        let mut buf = vec![];
+        let options = HdvTextWriterOptions {
+            is_csv_header: true,
+        };
+        let mut writer = HdvTextWriter::new(&mut buf, options);
+        for run in &runs {
+            writer.write(run).unwrap();
+        }
+        writer.flush().unwrap();
+        let text = String::from_utf8(buf).unwrap();
+        assert_eq!(
+            text.lines().nth(1).unwrap(),
+            "1970-01-01T00:00:01.500000Z,PT0.002250S,,"
+        );
+        assert_eq!(
+            text.lines().nth(2).unwrap(),
+            "1969-12-31T23:59:59.000000Z,PT0.000000S,1970-01-02T00:00:00.000000Z,"
+        );
+
+        let mut buf = vec![];
+        let options = HdvTextWriterOptions {
+            is_csv_header: false,
+        };
+        let mut writer = HdvTextWriter::new(&mut buf, options);
+        for run in &runs {
+            writer.write(run).unwrap();
+        }
+        writer.flush().unwrap();
+        let mut reader = HdvTextReader::new(std::io::Cursor::new(&buf));
+        for run in &runs {
+            let run_: Run = reader.read().unwrap();
+            assert_eq!(run, &run_);
+        }
+
+        // Columns in coarser units are rescaled
+        let header = vec![
+            AtomScheme {
+                name: "started_at".into(),
+                r#type: AtomType::Timestamp {
+                    unit: TimeUnit::Milliseconds,
+                    tz: Some("UTC".into()),
+                },
+            },
+            AtomScheme {
+                name: "elapsed".into(),
+                r#type: AtomType::Duration {
+                    unit: TimeUnit::Microseconds,
+                },
+            },
+        ];
+        let mut buf = vec![];
+        let mut writer = HdvBinRawWriter::new(&mut buf, header);
+        let row = ValueRow::new(vec![
+            Some(AtomValue::Timestamp(1_500)),
+            Some(AtomValue::Duration(2_250)),
+        ]);
+        writer.write(&row).unwrap();
+        writer.flush().unwrap();
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        let run: Run = reader.read().unwrap();
+        assert_eq!(run, runs[0]);
+    }
+
+    #[test]
+    fn test_derive_int128() {
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct Counter {
+            total: u128,
+            delta: i128,
+            peak: Option<u128>,
+        }
+
+        let counters = [
+            Counter {
+                total: u128::MAX,
+                delta: i128::MIN,
+                peak: None,
+            },
+            Counter {
+                total: 0,
+                delta: -1,
+                peak: Some(u64::MAX as u128 + 1),
+            },
+        ];
+
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        for counter in &counters {
+            writer.write(counter).unwrap();
+        }
+        writer.flush().unwrap();
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        for counter in &counters {
+            let counter_: Counter = reader.read().unwrap();
+            assert_eq!(counter, &counter_);
+        }
+
+        let mut buf = vec![];
+        let options = HdvTextWriterOptions {
+            is_csv_header: false,
+        };
+        let mut writer = HdvTextWriter::new(&mut buf, options);
+        for counter in &counters {
+            writer.write(counter).unwrap();
+        }
+        writer.flush().unwrap();
+        let text = String::from_utf8(buf.clone()).unwrap();
+        assert!(text.ends_with(&format!(
+            "{},{},,\n0,-1,18446744073709551616,\n",
+            u128::MAX,
+            i128::MIN
+        )));
+        let mut reader = HdvTextReader::new(std::io::Cursor::new(&buf));
+        for counter in &counters {
+            let counter_: Counter = reader.read().unwrap();
+            assert_eq!(counter, &counter_);
+        }
+    }
+
+    #[test]
+    fn test_uuid() {
+        let header = vec![AtomScheme {
+            name: "id".into(),
+            r#type: AtomType::Uuid,
+        }];
+        let id = [
+            0x67, 0xe5, 0x50, 0x44, 0x10, 0xb1, 0x42, 0x6f, 0x92, 0x47, 0xbb, 0x68, 0x0e, 0x5f,
+            0xe0, 0xc8,
+        ];
+        let row = ValueRow::new(vec![Some(AtomValue::Uuid(id))]);
+
+        let mut buf = vec![];
+        let mut writer = HdvBinRawWriter::new(&mut buf, header.clone());
+        writer.write(&row).unwrap();
+        writer.flush().unwrap();
+        let mut reader = HdvBinRawReader::new(std::io::Cursor::new(&buf));
+        assert_eq!(reader.read().unwrap(), row);
+
+        let mut buf = vec![];
+        let options = HdvTextWriterOptions {
+            is_csv_header: false,
+        };
+        let mut writer = HdvTextRawWriter::new(&mut buf, header, options);
+        writer.write(&row).unwrap();
+        writer.flush().unwrap();
+        let text = String::from_utf8(buf.clone()).unwrap();
+        assert!(text.ends_with("\n67e55044-10b1-426f-9247-bb680e5fe0c8,\n"));
+        let mut reader = HdvTextRawReader::new(std::io::Cursor::new(&buf));
+        assert_eq!(reader.read().unwrap(), row);
+    }
+
+    #[test]
+    fn test_derive_int_width() {
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct Port {
+            number: u16,
+            offset: Option<i8>,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct WidePort {
+            number: u64,
+            offset: Option<i64>,
+        }
+
+        let schemes = Port::object_scheme().atom_schemes();
+        assert_eq!(schemes[0].r#type, AtomType::U16);
+        assert_eq!(schemes[1].r#type, AtomType::I8);
+
+        let ports = [
+            Port {
+                number: 443,
+                offset: Some(-1),
+            },
+            Port {
+                number: u16::MAX,
+                offset: None,
+            },
+        ];
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        for port in &ports {
+            writer.write(port).unwrap();
+        }
+        writer.flush().unwrap();
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        for port in &ports {
+            let port_: Port = reader.read().unwrap();
+            assert_eq!(port, &port_);
+        }
+
+        // Wider columns are checked on read
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        for (number, offset) in [(80, Some(1)), (70000, None), (80, Some(-200))] {
+            writer.write(&WidePort { number, offset }).unwrap();
+        }
+        writer.flush().unwrap();
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        let port: Port = reader.read().unwrap();
+        assert_eq!(
+            port,
+            Port {
+                number: 80,
+                offset: Some(1)
+            }
+        );
+        let err = reader.read().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(reader.read().is_err());
+
+        let mut buf = vec![];
+        let options = HdvTextWriterOptions {
+            is_csv_header: false,
+        };
+        let mut writer = HdvTextWriter::new(&mut buf, options);
+        writer.write(&ports[0]).unwrap();
+        writer.flush().unwrap();
+        let text = String::from_utf8(buf).unwrap();
+        let header = text.lines().next().unwrap();
+        let text = format!("{header}\n70000,,\n");
+        let mut reader = HdvTextReader::<_, Port>::new(std::io::Cursor::new(text.as_bytes()));
+        assert!(reader.read().is_err());
+    }
+
+    #[test]
+    fn test_decimal() {
+        let header = vec![AtomScheme {
+            name: "amount".into(),
+            r#type: AtomType::Decimal {
+                precision: 10,
+                scale: 2,
+            },
+        }];
+        let rows = [-12345, 5, 0].map(|x| ValueRow::new(vec![Some(AtomValue::Decimal(x))]));
+
+        let mut buf = vec![];
+        let mut writer = HdvBinRawWriter::new(&mut buf, header.clone());
+        for row in &rows {
+            writer.write(row).unwrap();
+        }
+        writer.flush().unwrap();
+        let mut reader = HdvBinRawReader::new(std::io::Cursor::new(&buf));
+        for row in &rows {
+            assert_eq!(&reader.read().unwrap(), row);
+        }
+
+        let mut buf = vec![];
+        let options = HdvTextWriterOptions {
+            is_csv_header: false,
+        };
+        let mut writer = HdvTextRawWriter::new(&mut buf, header, options);
+        for row in &rows {
+            writer.write(row).unwrap();
+        }
+        writer.flush().unwrap();
+        let text = String::from_utf8(buf.clone()).unwrap();
+        assert!(text.ends_with("\n-123.45,\n0.05,\n0.00,\n"));
+        let mut reader = HdvTextRawReader::new(std::io::Cursor::new(&buf));
+        for row in &rows {
+            assert_eq!(&reader.read().unwrap(), row);
+        }
+
+        let header = text.lines().next().unwrap();
+        let text = format!("{header}\n1.5,\n1.234,\n123456789,\n");
+        let mut reader = HdvTextRawReader::new(std::io::Cursor::new(text.as_bytes()));
+        assert_eq!(
+            reader.read().unwrap(),
+            ValueRow::new(vec![Some(AtomValue::Decimal(150))])
+        );
+        // Too many decimal places
+        assert!(reader.read().is_err());
+        // Too many digits
+        assert!(reader.read().is_err());
+    }
+
+    #[test]
+    fn test_derive_map() {
+        #[derive(Debug, HdvSerde, PartialEq)]
+        #[hdv(borrow)]
+        struct Request {
+            headers: HashMap<Arc<str>, Arc<str>>,
+            labels: BTreeMap<String, u64>,
+            extra: Option<BTreeMap<String, f64>>,
+        }
+
+        let requests = [
+            Request {
+                headers: HashMap::from([("host".into(), "a.b".into())]),
+                labels: BTreeMap::from([("x".to_string(), 1), ("y".to_string(), 2)]),
+                extra: None,
+            },
+            Request {
+                headers: HashMap::new(),
+                labels: BTreeMap::new(),
+                extra: Some(BTreeMap::from([("z".to_string(), 0.5)])),
+            },
+        ];
+        assert_eq!(
+            Request::object_scheme().atom_schemes()[1].r#type,
+            AtomType::Map(ItemType::String, ItemType::U64)
+        );
+
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        for request in &requests {
+            writer.write(request).unwrap();
+        }
+        writer.flush().unwrap();
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        for request in &requests {
+            let request_: Request = reader.read().unwrap();
+            assert_eq!(request, &request_);
+        }
+        let mut reader = HdvBinReader::<_, Request>::new(std::io::Cursor::new(&buf));
+        for request in &requests {
+            assert_eq!(request, &reader.read_borrowed().unwrap());
+        }
+
+        let mut buf = vec![];
+        let options = HdvTextWriterOptions {
+            is_csv_header: false,
+        };
+        let mut writer = HdvTextWriter::new(&mut buf, options);
+        for request in &requests {
+            writer.write(request).unwrap();
+        }
+        writer.flush().unwrap();
+        let text = String::from_utf8(buf.clone()).unwrap();
+        assert!(text.ends_with("{host:a.b},{x:1;y:2},,\n{},{},{z:0.5},\n"));
+        let mut reader = HdvTextReader::new(std::io::Cursor::new(&buf));
+        for request in &requests {
+            let request_: Request = reader.read().unwrap();
+            assert_eq!(request, &request_);
+        }
+
+        let request = Request {
+            headers: HashMap::from([("a:b".into(), "c".into())]),
+            labels: BTreeMap::new(),
+            extra: None,
+        };
+        let mut writer = HdvTextWriter::new(
+            vec![],
+            HdvTextWriterOptions {
+                is_csv_header: false,
+            },
+        );
+        assert!(writer.write(&request).is_err());
+    }
+
+    #[test]
+    fn test_nullable_object() {
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct Contact {
+            email: Option<String>,
+            phone: Option<u64>,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct User {
+            id: u64,
+            contact: Option<Contact>,
+        }
+
+        let names = User::object_scheme()
+            .atom_schemes()
+            .into_iter()
+            .map(|x| (x.name, x.r#type))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                ("id".to_string(), AtomType::U64),
+                ("contact".to_string(), AtomType::Bool),
+                ("contact.email".to_string(), AtomType::String),
+                ("contact.phone".to_string(), AtomType::U64),
+            ]
+        );
+
+        let users = [
+            User {
+                id: 1,
+                contact: None,
+            },
+            User {
+                id: 2,
+                contact: Some(Contact {
+                    email: None,
+                    phone: None,
+                }),
+            },
+            User {
+                id: 3,
+                contact: Some(Contact {
+                    email: Some("a@b.c".into()),
+                    phone: None,
+                }),
+            },
+        ];
+
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        for user in &users {
+            writer.write(user).unwrap();
+        }
+        writer.flush().unwrap();
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        for user in &users {
+            let user_: User = reader.read().unwrap();
+            assert_eq!(user, &user_);
+        }
+
+        let mut buf = vec![];
+        let options = HdvTextWriterOptions {
+            is_csv_header: false,
+        };
+        let mut writer = HdvTextWriter::new(&mut buf, options);
+        for user in &users {
+            writer.write(user).unwrap();
+        }
+        writer.flush().unwrap();
+        let mut reader = HdvTextReader::<_, User>::new(std::io::Cursor::new(&buf));
+        for user in &users {
+            let user_ = reader.read().unwrap();
+            assert_eq!(user, &user_);
+        }
+
+        // Files without the validity column infer it from the nested columns
+        let header = vec![
+            AtomScheme {
+                name: "id".into(),
+                r#type: AtomType::U64,
+            },
+            AtomScheme {
+                name: "contact.phone".into(),
+                r#type: AtomType::U64,
+            },
+        ];
+        let mut buf = vec![];
+        let mut writer = HdvBinRawWriter::new(&mut buf, header);
+        writer
+            .write(&ValueRow::new(vec![
+                Some(AtomValue::U64(4)),
+                Some(AtomValue::U64(5)),
+            ]))
+            .unwrap();
+        writer.flush().unwrap();
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        let user: User = reader.read().unwrap();
+        assert_eq!(
+            user,
+            User {
+                id: 4,
+                contact: Some(Contact {
+                    email: None,
+                    phone: Some(5),
+                }),
+            }
+        );
+
+        // A present object must hold its non-nullable fields
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct Owner {
+            name: String,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct Account {
+            id: u64,
+            owner: Option<Owner>,
+        }
+        let mut buf = vec![];
+        let mut writer = HdvBinRawWriter::new(&mut buf, Account::object_scheme().atom_schemes());
+        writer
+            .write(&ValueRow::new(vec![
+                Some(AtomValue::U64(1)),
+                Some(AtomValue::Bool(false)),
+                None,
+            ]))
+            .unwrap();
+        writer
+            .write(&ValueRow::new(vec![
+                Some(AtomValue::U64(2)),
+                Some(AtomValue::Bool(true)),
+                None,
+            ]))
+            .unwrap();
+        writer.flush().unwrap();
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        let account: Account = reader.read().unwrap();
+        assert_eq!(account, Account { id: 1, owner: None });
+        let e = reader.read().map(|_: Account| ()).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_dynamic_record() {
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct Contact {
+            email: Option<String>,
+            phone: Option<u64>,
+        }
+        #[derive(Debug, HdvSerde, PartialEq)]
+        struct User {
+            id: u64,
+            contact: Option<Contact>,
+        }
+
+        let scheme = Arc::new(DynamicScheme::new(User::object_scheme()));
+        let mut record = DynamicRecord::new(Arc::clone(&scheme));
+        record.set("id", Some(AtomValue::U64(1))).unwrap();
+        let mut records = vec![record.clone()];
+        record
+            .set("contact.email", Some(AtomValue::String("a@b.c".into())))
+            .unwrap();
+        records.push(record.clone());
+        assert_eq!(record.get("contact").unwrap(), Some(&AtomValue::Bool(true)));
+        assert_eq!(
+            record
+                .set("contact.phone", Some(AtomValue::String("5".into())))
+                .unwrap_err()
+                .kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            record.get("contact.name").unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+        let users = [
+            User {
+                id: 1,
+                contact: None,
+            },
+            User {
+                id: 1,
+                contact: Some(Contact {
+                    email: Some("a@b.c".into()),
+                    phone: None,
+                }),
+            },
+        ];
+
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        for record in &records {
+            writer.write(record).unwrap();
+        }
+        writer.flush().unwrap();
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        for user in &users {
+            let user_: User = reader.read().unwrap();
+            assert_eq!(user, &user_);
+        }
+
+        let mut buf = vec![];
+        let options = HdvTextWriterOptions {
+            is_csv_header: false,
+        };
+        let mut writer = HdvTextWriter::new(&mut buf, options);
+        for record in &records {
+            writer.write(record).unwrap();
+        }
+        writer.flush().unwrap();
+        let mut reader = HdvTextReader::<_, User>::new(std::io::Cursor::new(&buf));
+        for user in &users {
+            let user_ = reader.read().unwrap();
+            assert_eq!(user, &user_);
+        }
+
+        // Typed files read back into dynamic records
+        let mut buf = vec![];
+        let mut writer = HdvBinWriter::new(&mut buf);
+        for user in &users {
+            writer.write(user).unwrap();
+        }
+        writer.flush().unwrap();
+        let mut reader = HdvBinReader::new(std::io::Cursor::new(&buf));
+        for record in &records {
+            let record_: DynamicRecord = reader.read_seed(&scheme).unwrap();
+            assert_eq!(record, &record_);
+        }
+    }
+
+    #[test]
+    fn test_codegen() {
+        mod generated {
+            include!(concat!(env!("OUT_DIR"), "/users.rs"));
+        }
+        use generated::{User, UserContact, UserPos};
+
+        let path = concat!(env!("OUT_DIR"), "/users.hdv");
+        let file = std::fs::File::open(path).unwrap();
+        let mut reader = HdvBinReader::new(std::io::BufReader::new(file));
+        let user: User = reader.read().unwrap();
+        assert_eq!(
+            user,
+            User {
+                id: Some(1),
+                name: Some("a".into()),
+                contact: Some(UserContact {
+                    email: Some("a@b.c".into()),
+                    phone_number: None,
+                }),
+                pos: UserPos { x: Some(1.5) },
+            }
+        );
+    }
 }